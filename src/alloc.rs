@@ -0,0 +1,207 @@
+use std::fmt::{Display, Formatter};
+
+/// Heap usage of a single phase, measured on the thread the phase ran on
+///
+/// Only collected when built with the `alloc-profile` feature, which installs a counting global
+/// allocator. Allocations made by any threads the phase spawns itself aren't counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct AllocStats {
+    /// Number of allocations, including reallocations
+    pub allocations: u64,
+
+    /// Total bytes requested across all allocations
+    pub bytes: u64,
+
+    /// Highest heap usage above the usage when the phase started
+    pub peak_bytes: u64,
+}
+
+/// A byte count with a binary unit, e.g. `1.5KiB`
+pub struct Bytes(pub u64);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{}B", self.0);
+        }
+
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.1}{}", value, UNITS[unit])
+    }
+}
+
+impl Display for AllocStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} / {} / {}",
+            self.allocations,
+            Bytes(self.bytes),
+            Bytes(self.peak_bytes)
+        )
+    }
+}
+
+/// Run `f`, measuring its allocations on the current thread if allocation profiling is enabled
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    #[cfg(feature = "alloc-profile")]
+    {
+        let start = counting::start();
+        let value = f();
+        (value, Some(counting::since(start)))
+    }
+
+    #[cfg(not(feature = "alloc-profile"))]
+    {
+        (f(), None)
+    }
+}
+
+#[cfg(feature = "alloc-profile")]
+pub use counting::CountingAllocator;
+
+#[cfg(feature = "alloc-profile")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::AllocStats;
+
+    struct Counters {
+        allocations: Cell<u64>,
+        bytes: Cell<u64>,
+
+        /// Signed, as memory allocated on another thread may be freed on this one
+        current: Cell<i64>,
+        peak: Cell<i64>,
+    }
+
+    thread_local! {
+        // Const initialised, so accessing it never allocates
+        static COUNTERS: Counters = const {
+            Counters {
+                allocations: Cell::new(0),
+                bytes: Cell::new(0),
+                current: Cell::new(0),
+                peak: Cell::new(0),
+            }
+        };
+    }
+
+    fn record(allocated: usize, freed: usize) {
+        // Fails during thread teardown, when there's nothing left to measure anyway
+        let _ = COUNTERS.try_with(|counters| {
+            if allocated > 0 {
+                counters.allocations.set(counters.allocations.get() + 1);
+                counters.bytes.set(counters.bytes.get() + allocated as u64);
+            }
+
+            let current = counters.current.get() + allocated as i64 - freed as i64;
+            counters.current.set(current);
+            counters.peak.set(counters.peak.get().max(current));
+        });
+    }
+
+    /// Wraps the system allocator, counting allocations made by each thread
+    pub struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                record(layout.size(), 0);
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                record(layout.size(), 0);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            record(0, layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                record(new_size, layout.size());
+            }
+            new_ptr
+        }
+    }
+
+    /// The counters at the start of a measurement
+    pub struct Start {
+        allocations: u64,
+        bytes: u64,
+        current: i64,
+    }
+
+    pub fn start() -> Start {
+        COUNTERS.with(|counters| {
+            // Peak usage is measured from here
+            counters.peak.set(counters.current.get());
+
+            Start {
+                allocations: counters.allocations.get(),
+                bytes: counters.bytes.get(),
+                current: counters.current.get(),
+            }
+        })
+    }
+
+    pub fn since(start: Start) -> AllocStats {
+        COUNTERS.with(|counters| AllocStats {
+            allocations: counters.allocations.get() - start.allocations,
+            bytes: counters.bytes.get() - start.bytes,
+            peak_bytes: (counters.peak.get() - start.current).max(0) as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(Bytes(512).to_string(), "512B");
+        assert_eq!(Bytes(1536).to_string(), "1.5KiB");
+        assert_eq!(Bytes(3 * 1024 * 1024).to_string(), "3.0MiB");
+    }
+
+    #[cfg(feature = "alloc-profile")]
+    #[test]
+    fn test_measure() {
+        let (_, stats) = measure(|| {
+            let big = vec![0u8; 4096];
+            drop(big);
+            let small = Vec::<u8>::with_capacity(100);
+            small.capacity()
+        });
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.allocations, 2);
+        assert_eq!(stats.bytes, 4196);
+        assert_eq!(stats.peak_bytes, 4096);
+    }
+
+    #[cfg(not(feature = "alloc-profile"))]
+    #[test]
+    fn test_measure_disabled() {
+        assert_eq!(measure(|| 5), (5, None));
+    }
+}
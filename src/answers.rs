@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{Outcome, RunResult};
+
+/// The result of comparing a part's answer against the stored expected answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AnswerStatus {
+    Correct,
+    Wrong {
+        expected: String,
+    },
+    /// No expected answer is known for this part
+    Unknown,
+}
+
+impl AnswerStatus {
+    pub fn check(expected: Option<&str>, actual: Option<&str>) -> Self {
+        match expected {
+            Some(expected) if Some(expected) == actual => Self::Correct,
+            Some(expected) => Self::Wrong {
+                expected: expected.to_string(),
+            },
+            None => Self::Unknown,
+        }
+    }
+
+    /// Short marker appended to a result cell in the text table
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Self::Correct => "✓",
+            Self::Wrong { .. } => "✗",
+            Self::Unknown => "?",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Correct => "correct",
+            Self::Wrong { .. } => "wrong",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartAnswers {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_1: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_2: Option<String>,
+}
+
+impl PartAnswers {
+    pub fn part(&self, part: u8) -> Option<&str> {
+        match part {
+            1 => self.part_1.as_deref(),
+            2 => self.part_2.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Load answers for a single input, returning `None` if the file doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = toml::to_string(self).expect("Answers are always representable as TOML");
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record the results of the given run as the known-correct answers
+    ///
+    /// Parts that didn't produce an answer keep any previously recorded answer. Returns whether
+    /// any recorded answer changed.
+    pub fn record(&mut self, result: &RunResult) -> bool {
+        let before = self.clone();
+        if let Some(answer) = result.p1_result.answer() {
+            self.part_1 = Some(answer.to_string());
+        }
+        if let Some(answer) = result.p2_result.answer() {
+            self.part_2 = Some(answer.to_string());
+        }
+        *self != before
+    }
+
+    /// Compare the given run against these answers, filling in its answer statuses
+    ///
+    /// Parts that were skipped are left unchecked.
+    pub fn check(&self, result: &mut RunResult) {
+        let check = |expected: &Option<String>, outcome: &Outcome| match outcome {
+            Outcome::Skipped => None,
+            _ => Some(AnswerStatus::check(expected.as_deref(), outcome.answer())),
+        };
+
+        result.p1_status = check(&self.part_1, &result.p1_result);
+        result.p2_status = check(&self.part_2, &result.p2_result);
+    }
+}
+
+/// Parse a TOML table keyed by day, in the form `[day_01]`
+pub(crate) fn parse_days<T: DeserializeOwned>(s: &str) -> anyhow::Result<BTreeMap<u8, T>> {
+    let raw: BTreeMap<String, T> = toml::from_str(s)?;
+
+    let mut days = BTreeMap::new();
+    for (key, value) in raw {
+        let day = key
+            .strip_prefix("day_")
+            .and_then(|day| day.parse().ok())
+            .with_context(|| format!("Invalid day key {key:?}, expected e.g. \"day_01\""))?;
+        days.insert(day, value);
+    }
+
+    Ok(days)
+}
+
+pub(crate) fn days_to_toml<T: Serialize>(days: &BTreeMap<u8, T>) -> String {
+    let raw = days
+        .iter()
+        .map(|(day, value)| (format!("day_{:02}", day), value))
+        .collect::<BTreeMap<_, _>>();
+
+    toml::to_string(&raw).expect("Per-day tables are always representable as TOML")
+}
+
+/// Known-correct answers for each day, stored as TOML in the form:
+///
+/// ```toml
+/// [day_01]
+/// part_1 = "1873376"
+/// part_2 = "18997088"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnswerStore {
+    days: BTreeMap<u8, PartAnswers>,
+}
+
+impl AnswerStore {
+    /// Load the store from the given path, treating a missing file as an empty store
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_toml_string())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            days: parse_days(s)?,
+        })
+    }
+
+    pub fn to_toml_string(&self) -> String {
+        days_to_toml(&self.days)
+    }
+
+    pub fn get(&self, day: u8) -> Option<&PartAnswers> {
+        self.days.get(&day)
+    }
+
+    /// Record a single known-correct answer, e.g. one accepted on submission
+    pub fn set(&mut self, day: u8, part: u8, answer: String) {
+        let answers = self.days.entry(day).or_default();
+        match part {
+            1 => answers.part_1 = Some(answer),
+            2 => answers.part_2 = Some(answer),
+            _ => panic!("Invalid part {part}"),
+        }
+    }
+
+    /// Record the results of the given run as the known-correct answers for its day, returning
+    /// whether any recorded answer changed
+    pub fn record(&mut self, result: &RunResult) -> bool {
+        self.days.entry(result.name.day).or_default().record(result)
+    }
+
+    /// Compare the given run against the stored answers, filling in its answer statuses
+    pub fn check(&self, result: &mut RunResult) {
+        self.get(result.name.day)
+            .cloned()
+            .unwrap_or_default()
+            .check(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::day;
+
+    const TEST_STORE: &str = r#"[day_01]
+part_1 = "11"
+part_2 = "31"
+
+[day_10]
+part_1 = "36"
+"#;
+
+    #[test]
+    fn test_roundtrip() {
+        let store = AnswerStore::parse(TEST_STORE).unwrap();
+        assert_eq!(store.get(1).unwrap().part_2.as_deref(), Some("31"));
+        assert_eq!(store.get(10).unwrap().part_2, None);
+        assert_eq!(store.get(2), None);
+
+        assert_eq!(store.to_toml_string(), TEST_STORE);
+    }
+
+    #[test]
+    fn test_record() {
+        let day = day(2);
+        let result = day.run(example!(2, 1), Default::default());
+
+        let mut store = AnswerStore::parse(TEST_STORE).unwrap();
+        assert!(store.record(&result));
+        assert!(!store.record(&result));
+        assert_eq!(store.get(2).unwrap().part_1.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        assert!(AnswerStore::parse("[one]\npart_1 = \"1\"\n").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(
+            AnswerStatus::check(Some("11"), Some("11")),
+            AnswerStatus::Correct
+        );
+        assert_eq!(
+            AnswerStatus::check(Some("11"), Some("0")),
+            AnswerStatus::Wrong {
+                expected: "11".to_string()
+            }
+        );
+        assert_eq!(
+            AnswerStatus::check(Some("11"), None),
+            AnswerStatus::Wrong {
+                expected: "11".to_string()
+            }
+        );
+        assert_eq!(AnswerStatus::check(None, Some("0")), AnswerStatus::Unknown);
+    }
+}
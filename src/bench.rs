@@ -0,0 +1,263 @@
+use std::time::{Duration, Instant};
+
+use crate::runner::{run_phase, PhaseOutput};
+use crate::select::Parts;
+use crate::{DayName, ErasedDay, Outcome, ParsedHandle};
+
+/// Summary statistics over a set of timing samples for a single phase
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub samples: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        assert!(!samples.is_empty(), "Can't compute stats over zero samples");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        let n = sorted.len();
+        let mean_secs = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / n as f64;
+        let variance = sorted
+            .iter()
+            .map(|s| (s.as_secs_f64() - mean_secs).powi(2))
+            .sum::<f64>()
+            / n as f64;
+
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        };
+
+        // Nearest-rank percentile
+        let p95_idx = ((n as f64 * 0.95).ceil() as usize).clamp(1, n) - 1;
+
+        Self {
+            samples: n,
+            min: sorted[0],
+            median,
+            mean: Duration::from_secs_f64(mean_secs),
+            p95: sorted[p95_idx],
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Number of untimed runs before sampling starts
+    pub warmup: usize,
+
+    /// Maximum number of timed runs
+    pub samples: usize,
+
+    /// Stop sampling early once this much time has been spent on timed runs
+    pub time_budget: Option<Duration>,
+
+    /// Which parts to run, any others are left out of the results
+    pub parts: Parts,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            warmup: 3,
+            samples: 100,
+            time_budget: None,
+            parts: Parts::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BenchResult {
+    pub name: DayName,
+    pub parse: Stats,
+
+    /// Missing for parts that weren't selected, or couldn't run because the parse failed
+    pub p1: Option<Stats>,
+    pub p2: Option<Stats>,
+    pub p1_result: Outcome,
+    pub p2_result: Outcome,
+}
+
+pub fn bench_day(day: &dyn ErasedDay, input: &str, opts: &BenchOptions) -> BenchResult {
+    for _ in 0..opts.warmup {
+        day.run(input, opts.parts);
+    }
+
+    // An untimed run for the outcomes, the samples below discard their results
+    let result = day.run(input, opts.parts);
+
+    // Parts are sampled over a single parse, rather than re-parsing the input for every sample
+    let parsed = match run_phase(|| day.parse(input)).1 {
+        PhaseOutput::Done(Ok(parsed)) => Some(parsed),
+        _ => None,
+    };
+    let sample_part =
+        |selected: bool, part: fn(&dyn ErasedDay, &ParsedHandle) -> _| match (selected, &parsed) {
+            (true, Some(parsed)) => Some(run_phase(|| part(day, parsed)).0.time),
+            _ => None,
+        };
+
+    let mut parse_samples = Vec::with_capacity(opts.samples);
+    let mut p1_samples = Vec::with_capacity(opts.samples);
+    let mut p2_samples = Vec::with_capacity(opts.samples);
+
+    let sw = Instant::now();
+    loop {
+        parse_samples.push(run_phase(|| day.parse(input)).0.time);
+        p1_samples.extend(sample_part(opts.parts.part_1, |day, parsed| {
+            day.part_1(parsed)
+        }));
+        p2_samples.extend(sample_part(opts.parts.part_2, |day, parsed| {
+            day.part_2(parsed)
+        }));
+
+        if parse_samples.len() >= opts.samples
+            || opts
+                .time_budget
+                .is_some_and(|budget| sw.elapsed() >= budget)
+        {
+            break;
+        }
+    }
+
+    BenchResult {
+        name: day.name(),
+        parse: Stats::from_samples(&parse_samples),
+        p1: (!p1_samples.is_empty()).then(|| Stats::from_samples(&p1_samples)),
+        p2: (!p2_samples.is_empty()).then(|| Stats::from_samples(&p2_samples)),
+        p1_result: result.p1_result,
+        p2_result: result.p2_result,
+    }
+}
+
+pub fn print_bench_table(results: &[BenchResult]) {
+    if results.is_empty() {
+        return;
+    }
+
+    const HEADERS: [&str; 9] = [
+        "Day", "Name", "Phase", "Samples", "Min", "Median", "Mean", "p95", "Stddev",
+    ];
+
+    let mut rows = Vec::new();
+    for result in results {
+        for (phase, stats, outcome) in [
+            ("Parse", Some(&result.parse), None),
+            ("P1", result.p1.as_ref(), Some(&result.p1_result)),
+            ("P2", result.p2.as_ref(), Some(&result.p2_result)),
+        ] {
+            if outcome == Some(&Outcome::Skipped) {
+                continue;
+            }
+
+            // A part that couldn't run has no samples, so its stats are reported as missing
+            let stat = |f: fn(&Stats) -> Duration| {
+                stats.map_or_else(|| "-".to_string(), |stats| format!("{:?}", f(stats)))
+            };
+            rows.push([
+                format!("{:02}", result.name.day),
+                result.name.name.to_string(),
+                phase.to_string(),
+                stats.map_or(0, |stats| stats.samples).to_string(),
+                stat(|stats| stats.min),
+                stat(|stats| stats.median),
+                stat(|stats| stats.mean),
+                stat(|stats| stats.p95),
+                stat(|stats| stats.stddev),
+            ]);
+        }
+    }
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = std::cmp::max(*width, cell.len());
+        }
+    }
+
+    let format_row = |cells: &[&str]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let header = format_row(&HEADERS);
+    println!("{}", header);
+    println!("{}", "-".repeat(header.len()));
+
+    for row in &rows {
+        println!("{}", format_row(&row.each_ref().map(String::as_str)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::day;
+
+    fn micros(values: &[u64]) -> Vec<Duration> {
+        values.iter().copied().map(Duration::from_micros).collect()
+    }
+
+    #[test]
+    fn test_stats_odd() {
+        let stats = Stats::from_samples(&micros(&[5, 1, 3, 2, 4]));
+        assert_eq!(stats.samples, 5);
+        assert_eq!(stats.min, Duration::from_micros(1));
+        assert_eq!(stats.median, Duration::from_micros(3));
+        assert_eq!(stats.mean, Duration::from_micros(3));
+        assert_eq!(stats.p95, Duration::from_micros(5));
+        assert_eq!(stats.stddev.as_nanos(), 1414);
+    }
+
+    #[test]
+    fn test_stats_even() {
+        let stats = Stats::from_samples(&micros(&[4, 1, 3, 2]));
+        assert_eq!(stats.median, Duration::from_nanos(2500));
+        assert_eq!(stats.p95, Duration::from_micros(4));
+    }
+
+    #[test]
+    fn test_bench_missing_parts() {
+        let day = day(2);
+        let opts = BenchOptions {
+            warmup: 0,
+            samples: 5,
+            time_budget: None,
+            parts: Parts::only(1),
+        };
+
+        let result = bench_day(day.as_ref(), example!(2, 1), &opts);
+        assert_eq!(result.parse.samples, 5);
+        assert_eq!(result.p1.map(|stats| stats.samples), Some(5));
+        assert!(result.p2.is_none());
+
+        let result = bench_day(day.as_ref(), "not a report", &opts);
+        assert_eq!(result.parse.samples, 5);
+        assert!(result.p1.is_none());
+        assert!(matches!(result.p1_result, Outcome::Error(_)));
+    }
+
+    #[test]
+    fn test_stats_p95() {
+        let samples = micros(&(1..=100).collect::<Vec<_>>());
+        let stats = Stats::from_samples(&samples);
+        assert_eq!(stats.p95, Duration::from_micros(95));
+        assert_eq!(stats.min, Duration::from_micros(1));
+    }
+}
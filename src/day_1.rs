@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::error::{parse_number, InputError};
+
 pub struct Input {
     left: Vec<u64>,
     right: Vec<u64>,
@@ -11,18 +13,23 @@ impl AsRef<Input> for Input {
     }
 }
 
-pub fn parse(input: &str) -> Input {
+pub fn parse(input: &str) -> Result<Input, InputError> {
     // Input is in the form "L1 R1\nL2 R2\nL3 R3\n..."
     let mut left = Vec::new();
     let mut right = Vec::new();
 
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
         let mut parts = line.split_whitespace();
-        left.push(parts.next().unwrap().parse().unwrap());
-        right.push(parts.next().unwrap().parse().unwrap());
+        let (Some(l), Some(r), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(InputError::at_line(line_number, "Expected exactly two numbers"));
+        };
+
+        left.push(parse_number(line_number, line, l)?);
+        right.push(parse_number(line_number, line, r)?);
     }
 
-    Input { left, right }
+    Ok(Input { left, right })
 }
 
 pub fn solve_part_1(input: &Input) -> u64 {
@@ -1,9 +1,9 @@
-use std::collections::HashSet;
-
+use crate::error::InputError;
+use crate::util::map2d;
 use crate::util::{Map2d, Map2dExt, Vec2};
 
-pub fn parse(input: &str) -> Map2d<u8> {
-    Map2d::parse_grid(input, |c| c.to_digit(10).unwrap() as u8)
+pub fn parse(input: &str) -> Result<Map2d<u8>, InputError> {
+    Map2d::try_parse_grid(input, |c| c.to_digit(10).map(|d| d as u8))
 }
 
 fn adjacency(map: &Map2d<u8>, pos: Vec2) -> impl Iterator<Item = Vec2> + '_ {
@@ -15,66 +15,186 @@ fn adjacency(map: &Map2d<u8>, pos: Vec2) -> impl Iterator<Item = Vec2> + '_ {
         .filter(move |test| map.get(*test) == Some(map.get(pos).unwrap() + 1))
 }
 
-struct StackElement<I: Iterator<Item = Vec2>> {
-    pos: Vec2,
-    adj: I,
+/// The score and rating of every tile in a height map, found in a single pass over the heights
+/// from 9 down to 0, with each tile building on the results of its uphill neighbours
+pub struct TrailAnalysis<'a> {
+    map: &'a Map2d<u8>,
+
+    /// The number of distinct hiking trails from each tile to any 9
+    ratings: Map2d<u64>,
+
+    /// A bitset of the 9s reachable from each tile, `words` long per tile
+    reachable: Vec<u64>,
+    words: usize,
 }
 
-/// Count the number of unique 9's it is possible to reach with teh P1 pathing rules
-fn count_p1_paths(map: &Map2d<u8>, head: Vec2) -> u64 {
-    let mut stack = Vec::with_capacity(10);
-    let mut nines: HashSet<Vec2> = Default::default();
-
-    stack.push(StackElement {
-        pos: head,
-        adj: adjacency(map, head),
-    });
-
-    while !stack.is_empty() {
-        match stack.last_mut().unwrap().adj.next() {
-            Some(next_pos) => stack.push(StackElement {
-                pos: next_pos,
-                adj: adjacency(map, next_pos),
-            }),
-            None => {
-                if stack.len() == 10 {
-                    nines.insert(stack.last().unwrap().pos);
+impl<'a> TrailAnalysis<'a> {
+    pub fn new(map: &'a Map2d<u8>) -> Self {
+        let mut levels = vec![Vec::new(); 10];
+        for (idx, height) in map.data.iter().enumerate() {
+            if let Some(level) = levels.get_mut(*height as usize) {
+                level.push(map.pos_of(idx));
+            }
+        }
+
+        let words = levels[9].len().div_ceil(64);
+        let mut reachable = vec![0u64; map.data.len() * words];
+        let mut ratings = Map2d::new_default(map.size, 0);
+
+        for (bit, pos) in levels[9].iter().enumerate() {
+            let idx = map.index_of(*pos).unwrap();
+            reachable[idx * words + bit / 64] |= 1 << (bit % 64);
+            *ratings.get_mut(*pos).unwrap() = 1;
+        }
+
+        for level in levels[..9].iter().rev() {
+            for pos in level.iter().copied() {
+                let idx = map.index_of(pos).unwrap();
+                for next in adjacency(map, pos) {
+                    let next_idx = map.index_of(next).unwrap();
+                    for word in 0..words {
+                        reachable[idx * words + word] |= reachable[next_idx * words + word];
+                    }
+                    ratings.data[idx] += ratings.data[next_idx];
                 }
-                stack.pop();
             }
         }
+
+        Self {
+            map,
+            ratings,
+            reachable,
+            words,
+        }
     }
 
-    nines.len() as u64
+    pub fn trailheads(&self) -> impl Iterator<Item = Vec2> + 'a {
+        self.map.find_all(|height| *height == 0)
+    }
+
+    /// The number of distinct 9s reachable from `pos`
+    pub fn score(&self, pos: Vec2) -> u64 {
+        let Some(idx) = self.map.index_of(pos) else {
+            return 0;
+        };
+        self.reachable[idx * self.words..(idx + 1) * self.words]
+            .iter()
+            .map(|word| word.count_ones() as u64)
+            .sum()
+    }
+
+    /// The number of distinct hiking trails from `pos` to any 9
+    pub fn rating(&self, pos: Vec2) -> u64 {
+        self.ratings.get(pos).unwrap_or(0)
+    }
+
+    /// Every hiking trail from `head` to a 9, as the positions along it
+    pub fn trails(&self, head: Vec2) -> Vec<Vec<Vec2>> {
+        let mut trails = Vec::new();
+        if self.rating(head) == 0 {
+            return trails;
+        }
+
+        let mut trail = vec![head];
+        self.extend_trails(&mut trail, &mut trails);
+        trails
+    }
+
+    fn extend_trails(&self, trail: &mut Vec<Vec2>, trails: &mut Vec<Vec<Vec2>>) {
+        let pos = *trail.last().unwrap();
+        if self.map.get(pos) == Some(9) {
+            trails.push(trail.clone());
+            return;
+        }
+
+        // Only follow neighbours that lead to a 9, so no time is spent on dead ends
+        for next in adjacency(self.map, pos).filter(|next| self.rating(*next) > 0) {
+            trail.push(next);
+            self.extend_trails(trail, trails);
+            trail.pop();
+        }
+    }
 }
 
 pub fn solve_part_1(input: &Map2d<u8>) -> u64 {
-    input
-        .find_all(|x| *x == 0)
-        .map(|head| count_p1_paths(input, head))
-        .sum()
+    let analysis = TrailAnalysis::new(input);
+    analysis.trailheads().map(|head| analysis.score(head)).sum()
+}
+
+/// Record a frame of each trail from each trailhead when visualizing
+fn record_trails(analysis: &TrailAnalysis) {
+    if !map2d::is_recording() {
+        return;
+    }
+
+    for head in analysis.trailheads() {
+        for trail in analysis.trails(head) {
+            map2d::record_frame(|| {
+                analysis
+                    .map
+                    .frame(|height| [height * 20; 3])
+                    .path(trail.iter().copied(), [40, 200, 40])
+                    .point(head, [255, 40, 40])
+            });
+        }
+    }
 }
 
 pub fn solve_part_2(input: &Map2d<u8>) -> u64 {
-    0
+    let analysis = TrailAnalysis::new(input);
+    record_trails(&analysis);
+    analysis
+        .trailheads()
+        .map(|head| analysis.rating(head))
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"89010123
-78121874
-87430965
-96549874
-45678903
-32019012
-01329801
-10456732"#;
+    const TEST_INPUT: &str = example!(10, 5);
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&input), 36);
     }
+
+    #[test]
+    fn test_part_2() {
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(solve_part_2(&input), 81);
+    }
+
+    #[test]
+    fn test_trailheads() {
+        let input = parse(TEST_INPUT).unwrap();
+        let analysis = TrailAnalysis::new(&input);
+
+        let heads = analysis.trailheads().collect::<Vec<_>>();
+        let scores = heads.iter().map(|h| analysis.score(*h)).collect::<Vec<_>>();
+        let ratings = heads
+            .iter()
+            .map(|h| analysis.rating(*h))
+            .collect::<Vec<_>>();
+        assert_eq!(scores, [5, 6, 5, 3, 1, 3, 5, 3, 5]);
+        assert_eq!(ratings, [20, 24, 10, 4, 1, 4, 5, 8, 5]);
+
+        for head in heads {
+            let trails = analysis.trails(head);
+            assert_eq!(trails.len() as u64, analysis.rating(head));
+
+            for trail in trails.iter() {
+                let heights = trail.iter().map(|pos| input.get(*pos).unwrap());
+                assert!(heights.eq(0..=9));
+                assert!(trail.windows(2).all(|w| (w[1] - w[0]).l1_norm() == 1));
+            }
+
+            let mut ends = trails.iter().map(|t| t[9]).collect::<Vec<_>>();
+            ends.sort_by_key(|pos| (pos.y, pos.x));
+            ends.dedup();
+            assert_eq!(ends.len() as u64, analysis.score(head));
+        }
+    }
 }
@@ -1,11 +1,20 @@
 use std::collections::HashMap;
 
-pub fn parse(input: &str) -> Vec<i64> {
-    input
-        .split_ascii_whitespace()
-        .map(str::parse)
-        .collect::<Result<_, _>>()
-        .unwrap()
+use crate::error::{parse_number, InputError};
+
+pub fn parse(input: &str) -> Result<Vec<i64>, InputError> {
+    let mut stones = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        for num in line.split_ascii_whitespace() {
+            let stone: i64 = parse_number(idx + 1, line, num)?;
+            if stone < 0 {
+                return Err(InputError::in_line(idx + 1, line, num, "Negative stone"));
+            }
+            stones.push(stone);
+        }
+    }
+
+    Ok(stones)
 }
 
 /// If the given number has an even count of base 10 digits, returns the split
@@ -17,7 +26,7 @@ pub fn parse(input: &str) -> Vec<i64> {
 fn split_digits(num: i64) -> Option<(i64, i64)> {
     let digit_count = num.ilog10() + 1;
 
-    if digit_count % 2 == 0 {
+    if digit_count.is_multiple_of(2) {
         let size = 10i64.pow(digit_count / 2);
         let upper = num / size;
         let lower = num % size;
@@ -1,6 +1,7 @@
+use crate::error::InputError;
 use crate::util::{Dir, Map2d, Map2dExt, Vec2};
 
-pub fn parse(input: &str) -> Map2d<char> {
+pub fn parse(input: &str) -> Result<Map2d<char>, InputError> {
     Map2d::parse_grid(input, std::convert::identity)
 }
 
@@ -13,7 +14,6 @@ fn adjacency(map: &Map2d<char>, pos: Vec2) -> impl Iterator<Item = Vec2> + '_ {
 }
 
 struct StackElement<I: Iterator<Item = Vec2>> {
-    pos: Vec2,
     adj: I,
 }
 
@@ -74,7 +74,6 @@ fn region_cost(map: &Map2d<char>, visited: &mut Map2d<bool>, seed: Vec2, magic:
     *this_region.get_mut(seed).unwrap() = true;
 
     let mut stack = vec![StackElement {
-        pos: seed,
         adj: adjacency(map, seed),
     }];
 
@@ -94,7 +93,6 @@ fn region_cost(map: &Map2d<char>, visited: &mut Map2d<bool>, seed: Vec2, magic:
                 *this_region.get_mut(next_pos).unwrap() = true;
 
                 stack.push(StackElement {
-                    pos: next_pos,
                     adj: adjacency(map, next_pos),
                 });
             }
@@ -133,20 +131,11 @@ pub fn solve_part_2(input: &Map2d<char>) -> i64 {
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"RRRRIICCFF
-RRRRIICCCF
-VVRRRCCFFF
-VVRCCCJFFF
-VVVVCJJCFE
-VVIVCCJJEE
-VVIIICJJEE
-MIIIIIJJEE
-MIIISIJEEE
-MMMISSJEEE"#;
+    const TEST_INPUT: &str = example!(12, 3);
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&input), 1930);
     }
 }
@@ -1,3 +1,4 @@
+use crate::error::{parse_number, InputError};
 use crate::util::Vec2;
 
 #[derive(Debug)]
@@ -7,7 +8,7 @@ pub struct Machine {
     prize: Vec2,
 }
 
-pub fn parse(input: &str) -> Vec<Machine> {
+pub fn parse(input: &str) -> Result<Vec<Machine>, InputError> {
     // Input like:
     //      Button A: X+94, Y+34
     //      Button B: X+22, Y+67
@@ -17,26 +18,62 @@ pub fn parse(input: &str) -> Vec<Machine> {
     //      Button B: X+67, Y+21
     //      Prize: X=12748, Y=12176
 
-    fn parse_line(line: &str) -> Vec2 {
-        let (_, coords) = line.split_once(':').unwrap();
-        let (x, y) = coords.split_once(',').unwrap();
+    fn parse_line(
+        line_number: usize,
+        line: &str,
+        label: &str,
+        separator: char,
+    ) -> Result<Vec2, InputError> {
+        let coords = line
+            .strip_prefix(label)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .ok_or_else(|| InputError::at_line(line_number, format!("Expected \"{label}:\"")))?;
+        let (x, y) = coords
+            .split_once(',')
+            .ok_or_else(|| InputError::at_line(line_number, "Expected \"X..., Y...\""))?;
+
+        let coord = |s: &str, axis: char| {
+            let s = s.trim();
+            let num = s
+                .strip_prefix(axis)
+                .and_then(|s| s.strip_prefix(separator))
+                .ok_or_else(|| {
+                    InputError::in_line(line_number, line, s, format!("Expected \"{axis}{separator}\""))
+                })?;
+            parse_number(line_number, line, num)
+        };
+
+        Ok(Vec2 {
+            x: coord(x, 'X')?,
+            y: coord(y, 'Y')?,
+        })
+    }
+
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line))
+        .filter(|(_, line)| !line.is_empty());
+
+    let mut machines = Vec::new();
+    while let Some((a_line_number, a_line)) = lines.next() {
+        let a = parse_line(a_line_number, a_line, "Button A", '+')?;
+
+        let mut next_line = |label| {
+            lines.next().ok_or_else(|| {
+                InputError::at_line(a_line_number, format!("Machine is missing its \"{label}\" line"))
+            })
+        };
+        let (b_line_number, b_line) = next_line("Button B")?;
+        let (prize_line_number, prize_line) = next_line("Prize")?;
+
+        let b = parse_line(b_line_number, b_line, "Button B", '+')?;
+        let prize = parse_line(prize_line_number, prize_line, "Prize", '=')?;
 
-        let x = x.trim()[2..].parse().unwrap();
-        let y = y.trim()[2..].parse().unwrap();
-        Vec2 { x, y }
+        machines.push(Machine { a, b, prize });
     }
 
-    input
-        .split("\n\n")
-        .map(|section| {
-            let mut lines = section.lines();
-            let a = parse_line(lines.next().unwrap());
-            let b = parse_line(lines.next().unwrap());
-            let prize = parse_line(lines.next().unwrap());
-
-            Machine { a, b, prize }
-        })
-        .collect()
+    Ok(machines)
 }
 
 /// Find A, B, such that A*a + B*b = Prize
@@ -61,12 +98,12 @@ fn min_machine_presses(machine: &Machine) -> Option<Vec2> {
         return None;
     }
     
-    let A = (b.y * prize.x - b.x * prize.y) / det;
-    let B = (-a.y * prize.x + a.x * prize.y) / det;
+    let a_presses = (b.y * prize.x - b.x * prize.y) / det;
+    let b_presses = (-a.y * prize.x + a.x * prize.y) / det;
 
-    let soln = a * A + b * B;
+    let soln = a * a_presses + b * b_presses;
     if soln == prize {
-        Some(Vec2::new(A, B))
+        Some(Vec2::new(a_presses, b_presses))
     } else {
         // Rounding error -> non integer solution
         None
@@ -97,25 +134,23 @@ pub fn solve_part_2(input: &[Machine]) -> i64 {
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"Button A: X+94, Y+34
-Button B: X+22, Y+67
-Prize: X=8400, Y=5400
-
-Button A: X+26, Y+66
-Button B: X+67, Y+21
-Prize: X=12748, Y=12176
+    const TEST_INPUT: &str = example!(13, 1);
 
-Button A: X+17, Y+86
-Button B: X+84, Y+37
-Prize: X=7870, Y=6450
-
-Button A: X+69, Y+23
-Button B: X+27, Y+71
-Prize: X=18641, Y=10279"#;
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            parse("Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400, Y+5400").err(),
+            Some(InputError::at(3, 16, "Expected \"Y=\""))
+        );
+        assert_eq!(
+            parse("Button A: X+94, Y+34\nButton B: X+22, Y+67").err(),
+            Some(InputError::at_line(1, "Machine is missing its \"Prize\" line"))
+        );
+    }
 
     #[test]
     fn test_machine_solve() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(min_machine_presses(&input[0]), Some(Vec2::new(80, 40)));
         assert_eq!(min_machine_presses(&input[1]), None);
         assert_eq!(min_machine_presses(&input[2]), Some(Vec2::new(38, 86)));
@@ -124,7 +159,7 @@ Prize: X=18641, Y=10279"#;
     
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&input), 480)
     }
 }
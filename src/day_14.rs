@@ -1,4 +1,8 @@
-use crate::util::Vec2;
+use std::fmt::{Display, Formatter};
+
+use crate::error::{parse_number, InputError};
+use crate::util::map2d::{self, Frame};
+use crate::util::{crt, Map2d, Map2dExt, Vec2};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Robot {
@@ -6,24 +10,42 @@ pub struct Robot {
     vel: Vec2,
 }
 
-pub fn parse(input: &str) -> Vec<Robot> {
+pub fn parse(input: &str) -> Result<Vec<Robot>, InputError> {
     // Each line of input in form:
     //   p=<pos.x>,<pos.y> v=<vel.x>,<vel.y>
 
     input
         .lines()
-        .map(|line| {
-            let mut parts = line.split_whitespace();
-            let pos = parts.next().unwrap();
-            let vel = parts.next().unwrap();
+        .enumerate()
+        .map(|(idx, line)| {
+            let line_number = idx + 1;
 
-            let (pos_x, pos_y) = pos[2..].split_once(',').unwrap();
-            let pos = Vec2::new(pos_x.parse().unwrap(), pos_y.parse().unwrap());
+            let parse_vec = |part: Option<&str>, prefix: &str| -> Result<Vec2, InputError> {
+                let part = part.ok_or_else(|| {
+                    InputError::at_line(line_number, format!("Missing \"{prefix}\" vector"))
+                })?;
+                let (x, y) = part
+                    .strip_prefix(prefix)
+                    .and_then(|coords| coords.split_once(','))
+                    .ok_or_else(|| {
+                        InputError::in_line(
+                            line_number,
+                            line,
+                            part,
+                            format!("Expected \"{prefix}<x>,<y>\""),
+                        )
+                    })?;
+                Ok(Vec2::new(
+                    parse_number(line_number, line, x)?,
+                    parse_number(line_number, line, y)?,
+                ))
+            };
 
-            let (vel_x, vel_y) = vel[2..].split_once(',').unwrap();
-            let vel = Vec2::new(vel_x.parse().unwrap(), vel_y.parse().unwrap());
+            let mut parts = line.split_whitespace();
+            let pos = parse_vec(parts.next(), "p=")?;
+            let vel = parse_vec(parts.next(), "v=")?;
 
-            Robot { pos, vel }
+            Ok(Robot { pos, vel })
         })
         .collect()
 }
@@ -77,65 +99,169 @@ pub fn solve_part_1(input: &[Robot]) -> u64 {
     p1_inner(input, REAL_SIZE)
 }
 
-/// Test whether the given set of robots contains a vertical line at least 10 units long.
-fn p2_heuristic(robots: &[Robot], storage: &mut Vec<Vec2>) -> bool {
-    storage.clear();
-    storage.extend(robots.iter().map(|robot| robot.pos));
-    storage.sort_by_key(|pos| (pos.x, pos.y));
-    
-    let mut last = storage[0];
-    let mut count = 1;
-
-    for pos in storage.iter().skip(1) {
-        if pos.x == last.x && (pos.y == last.y || pos.y == last.y + 1) {
-            count += 1;
-        } else {
-            count = 1;
-        }
+/// Each robot's position after `steps` steps, jumping straight there rather than stepping
+fn positions_at(robots: &[Robot], size: Vec2, steps: u64) -> impl Iterator<Item = Vec2> + '_ {
+    // Positions along each axis repeat with the size of that axis, so reducing the steps and
+    // velocities first keeps the products small however many steps are taken
+    let axis = move |pos: i64, vel: i64, size: i64| {
+        let steps = (steps % size as u64) as i64;
+        signed_mod(pos + signed_mod(vel, size) * steps, size)
+    };
+    robots.iter().map(move |robot| {
+        Vec2::new(
+            axis(robot.pos.x, robot.vel.x, size.x),
+            axis(robot.pos.y, robot.vel.y, size.y),
+        )
+    })
+}
 
-        if count >= 10 {
-            return true;
+/// The step within `period` at which the values are least spread out, where each value is
+/// `(pos + vel * step) % period`
+fn min_variance_step(values: &[(i64, i64)], period: i64) -> i64 {
+    let n = values.len() as i64;
+    (0..period)
+        .min_by_key(|&step| {
+            let (sum, sum_sq) = values.iter().fold((0, 0), |(sum, sum_sq), (pos, vel)| {
+                let x = signed_mod(pos + vel * step, period);
+                (sum + x, sum_sq + x * x)
+            });
+            // The variance, scaled by n^2 to stay in integers
+            n * sum_sq - sum * sum
+        })
+        .unwrap_or(0)
+}
+
+/// Find the step at which the robots gather into a picture
+///
+/// Each robot's x position repeats every `size.x` steps and its y position every `size.y` steps,
+/// so the picture appears at the step in each period where the robots are least spread out along
+/// that axis. The two are combined with the Chinese remainder theorem into a step within the
+/// whole `size.x * size.y` cycle, which requires the sides to be coprime.
+fn find_tree(robots: &[Robot], size: Vec2) -> Option<i64> {
+    let xs = robots.iter().map(|r| (r.pos.x, r.vel.x)).collect::<Vec<_>>();
+    let ys = robots.iter().map(|r| (r.pos.y, r.vel.y)).collect::<Vec<_>>();
+    crt(
+        min_variance_step(&xs, size.x),
+        size.x,
+        min_variance_step(&ys, size.y),
+        size.y,
+    )
+}
+
+/// The robots' positions after some number of steps
+pub struct RobotPicture {
+    pub step: u64,
+    pub picture: Map2d<bool>,
+}
+
+impl RobotPicture {
+    fn new(robots: &[Robot], size: Vec2, step: u64) -> Self {
+        let mut picture = Map2d::new_default(size, false);
+        for pos in positions_at(robots, size, step) {
+            *picture.get_mut(pos).unwrap() = true;
         }
+        Self { step, picture }
+    }
 
-        last = *pos;
+    pub fn frame(&self) -> Frame {
+        self.picture.frame(|robot| if robot { [40, 200, 40] } else { [0; 3] })
     }
-    
-    false
 }
 
-pub fn solve_part_2(input: &[Robot]) -> u64 {
-    let mut robots = input.to_vec();
-    let mut storage = Vec::new();
-    let mut i = 0;
-    loop {
-        i += 1;
-        move_robots(&mut robots, REAL_SIZE);
-        if p2_heuristic(&robots, &mut storage) {
-            return i;
+impl Display for RobotPicture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.picture.data.chunks(self.picture.size.x as usize) {
+            let row = row
+                .iter()
+                .map(|&robot| if robot { '#' } else { '.' })
+                .collect::<String>();
+            writeln!(f, "{}", row)?;
         }
+        Ok(())
+    }
+}
+
+/// The robots in `input` after each of `steps`, or when they form a picture of a Christmas tree
+/// if `steps` is empty
+pub fn robot_pictures(input: &str, steps: &[u64]) -> Result<Vec<RobotPicture>, InputError> {
+    let robots = parse(input)?;
+    if !steps.is_empty() {
+        return Ok(steps
+            .iter()
+            .map(|&step| RobotPicture::new(&robots, REAL_SIZE, step))
+            .collect());
     }
+
+    Ok(vec![tree_picture(&robots)])
+}
+
+/// The robots when they first form a picture of a Christmas tree, whose step is the part 2
+/// answer
+fn tree_picture(robots: &[Robot]) -> RobotPicture {
+    let step = find_tree(robots, REAL_SIZE).expect("The real grid's sides are coprime");
+    RobotPicture::new(robots, REAL_SIZE, step as u64)
+}
+
+pub fn solve_part_2(input: &[Robot]) -> u64 {
+    let picture = tree_picture(input);
+    map2d::record_frame(|| picture.frame());
+    picture.step
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     
-    const TEST_INPUT: &str = r#"p=0,4 v=3,-3
-p=6,3 v=-1,-3
-p=10,3 v=-1,2
-p=2,0 v=2,-1
-p=0,0 v=1,3
-p=3,0 v=-2,-2
-p=7,6 v=-1,-3
-p=3,0 v=-1,-2
-p=9,3 v=2,3
-p=7,3 v=-1,2
-p=2,4 v=2,-3
-p=9,5 v=-3,-3"#;
+    const TEST_INPUT: &str = example!(14, 1);
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(p1_inner(&input, Vec2::new(11, 7)), 12);
     }
+
+    #[test]
+    fn test_positions_at() {
+        let input = parse(TEST_INPUT).unwrap();
+        let size = Vec2::new(11, 7);
+
+        let mut robots = input.clone();
+        for _ in 0..100 {
+            move_robots(&mut robots, size);
+        }
+        let stepped = robots.iter().map(|r| r.pos).collect::<Vec<_>>();
+        assert_eq!(positions_at(&input, size, 100).collect::<Vec<_>>(), stepped);
+
+        // Whole cycles of 11 * 7 steps leave every robot where it was
+        let steps = (u64::MAX / 77 - 2) * 77 + 100;
+        assert_eq!(positions_at(&input, size, steps).collect::<Vec<_>>(), stepped);
+    }
+
+    #[test]
+    fn test_find_tree() {
+        // A 10x10 block of robots that all come together after 5000 steps, amongst as many others
+        const STEP: i64 = 5000;
+        let mut robots = Vec::new();
+        for i in 0..200i64 {
+            let vel = Vec2::new((i * 37) % 97 - 48, (i * 53) % 89 - 44);
+            let pos = if i < 100 {
+                Vec2::new(40 + i % 10, 50 + i / 10) - vel * STEP
+            } else {
+                Vec2::new(i * 13, i * 29)
+            };
+            robots.push(Robot {
+                pos: Vec2::new(signed_mod(pos.x, 101), signed_mod(pos.y, 103)),
+                vel,
+            });
+        }
+
+        assert_eq!(find_tree(&robots, REAL_SIZE), Some(STEP));
+        assert_eq!(solve_part_2(&robots), STEP as u64);
+
+        let picture = RobotPicture::new(&robots, REAL_SIZE, STEP as u64);
+        let text = picture.to_string();
+        let rows = text.lines().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 103);
+        assert_eq!(&rows[55][38..52], "..##########..");
+    }
 }
@@ -1,10 +1,20 @@
-pub fn parse(input: &str) -> Vec<Vec<u64>> {
+use crate::error::{parse_number, InputError};
+
+pub fn parse(input: &str) -> Result<Vec<Vec<u64>>, InputError> {
     input
         .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|num| num.parse().unwrap())
-                .collect()
+        .enumerate()
+        .map(|(idx, line)| {
+            let report = line
+                .split_whitespace()
+                .map(|num| parse_number(idx + 1, line, num))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if report.is_empty() {
+                Err(InputError::at_line(idx + 1, "Empty report"))
+            } else {
+                Ok(report)
+            }
         })
         .collect()
 }
@@ -68,22 +78,17 @@ pub fn solve_part_2(input: &[Vec<u64>]) -> u64 {
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"7 6 4 2 1
-1 2 7 8 9
-9 7 6 2 1
-1 3 2 4 5
-8 6 4 4 1
-1 3 6 7 9"#;
+    const TEST_INPUT: &str = example!(2, 1);
 
     #[test]
     fn test_part_1() {
-        let test_input = parse(TEST_INPUT);
+        let test_input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&test_input), 2);
     }
 
     #[test]
     fn test_part_2() {
-        let test_input = parse(TEST_INPUT);
+        let test_input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_2(&test_input), 4);
     }
 }
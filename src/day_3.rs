@@ -1,3 +1,5 @@
+use crate::error::InputError;
+
 /// A poor-man's parser combinator implementation
 enum ParseResult<'a, T> {
     Ok { item: T, tail: &'a str },
@@ -14,7 +16,7 @@ impl<'a, T> ParseResult<'a, T> {
 }
 
 /// Skip a single any character
-fn parse_any(input: &str) -> ParseResult<()> {
+fn parse_any(input: &str) -> ParseResult<'_, ()> {
     ParseResult::Ok {
         item: (),
         tail: &input[1..],
@@ -29,16 +31,16 @@ fn parse_lit<'a>(input: &'a str, lit: &str) -> ParseResult<'a, ()> {
     }
 }
 
-fn parse_int(input: &str) -> ParseResult<u64> {
+fn parse_int(input: &str) -> ParseResult<'_, u64> {
     let int_len = input.chars().take_while(|c| c.is_ascii_digit()).count();
 
-    if int_len == 0 {
-        ParseResult::Fail { input }
-    } else {
-        ParseResult::Ok {
-            item: input[..int_len].parse().unwrap(),
+    match input[..int_len].parse() {
+        Ok(item) => ParseResult::Ok {
+            item,
             tail: &input[int_len..],
-        }
+        },
+        // Either no digits, or too many to fit in the integer
+        Err(_) => ParseResult::Fail { input },
     }
 }
 
@@ -50,7 +52,7 @@ pub struct MulStatement {
 
 // Poor man's combination, not going to bother writing a generic macro for all
 // cases like this
-fn parse_mul_statement(input: &str) -> ParseResult<MulStatement> {
+fn parse_mul_statement(input: &str) -> ParseResult<'_, MulStatement> {
     let tail = match parse_lit(input, "mul(") {
         ParseResult::Ok { item: _, tail } => tail,
         ParseResult::Fail { .. } => return ParseResult::Fail { input },
@@ -90,7 +92,7 @@ pub enum Alternation {
     Mul(MulStatement),
 }
 
-fn parse_alternation(input: &str) -> ParseResult<Alternation> {
+fn parse_alternation(input: &str) -> ParseResult<'_, Alternation> {
     match parse_lit(input, "do()") {
         ParseResult::Ok { item: _, tail } => {
             return ParseResult::Ok {
@@ -120,8 +122,11 @@ fn parse_alternation(input: &str) -> ParseResult<Alternation> {
     }
 }
 
-pub fn parse(mut input: &str) -> Vec<Alternation> {
-    assert!(input.is_ascii());
+pub fn parse(mut input: &str) -> Result<Vec<Alternation>, InputError> {
+    if let Some((idx, line)) = input.lines().enumerate().find(|(_, line)| !line.is_ascii()) {
+        let col = line.chars().position(|c| !c.is_ascii()).unwrap();
+        return Err(InputError::at(idx + 1, col + 1, "Non-ASCII character"));
+    }
 
     let mut symbols = Vec::new();
     while !input.is_empty() {
@@ -136,7 +141,7 @@ pub fn parse(mut input: &str) -> Vec<Alternation> {
         }
     }
 
-    symbols
+    Ok(symbols)
 }
 
 pub fn solve_part_1(input: &[Alternation]) -> u64 {
@@ -172,16 +177,14 @@ pub fn solve_part_2(input: &[Alternation]) -> u64 {
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str =
-        "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const TEST_INPUT: &str = example!(3, 1);
 
-    const TEST_INPUT_2: &str =
-        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+    const TEST_INPUT_2: &str = example!(3, 2);
 
     #[test]
     fn test_parse() {
         assert_eq!(
-            parse(TEST_INPUT_2),
+            parse(TEST_INPUT_2).unwrap(),
             [
                 Alternation::Mul(MulStatement { a: 2, b: 4 }),
                 Alternation::Dont,
@@ -195,11 +198,11 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(solve_part_1(&parse(TEST_INPUT)), 161)
+        assert_eq!(solve_part_1(&parse(TEST_INPUT).unwrap()), 161)
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(solve_part_2(&parse(TEST_INPUT_2)), 48)
+        assert_eq!(solve_part_2(&parse(TEST_INPUT_2).unwrap()), 48)
     }
 }
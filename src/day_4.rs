@@ -1,6 +1,7 @@
+use crate::error::InputError;
 use crate::util::{Map2d, Map2dExt, Vec2};
 
-pub fn parse(input: &str) -> Map2d<char> {
+pub fn parse(input: &str) -> Result<Map2d<char>, InputError> {
     Map2d::parse_grid(input, std::convert::identity)
 }
 
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::error::{parse_number, InputError};
+
 struct Rule {
     a: i32,
     b: i32,
@@ -40,7 +42,7 @@ impl AsRef<Input> for Input {
     }
 }
 
-pub fn parse(input: &str) -> Input {
+pub fn parse(input: &str) -> Result<Input, InputError> {
     // Input in the form of:
     // <rule_1_a>|<rule_1_b>
     // <rule_2_a>|<rule_2_b>
@@ -50,29 +52,44 @@ pub fn parse(input: &str) -> Input {
     // <update_2_1>,<update_2_2>,...,<update_2_m>
     // ...
 
-    let rules = input
-        .lines()
-        .take_while(|line| line.contains('|'))
-        .map(|line| {
-            let (a, b) = line.split_once('|').unwrap();
-            let a = a.parse().unwrap();
-            let b = b.parse().unwrap();
-            Rule { a, b }
-        })
-        .collect();
-
-    let updates = input
-        .lines()
-        .filter(|line| !line.is_empty())
-        .skip_while(|line| line.contains('|'))
-        .map(|line| {
-            line.split(',')
-                .map(|num| num.parse().unwrap())
-                .collect::<Vec<i32>>()
-        })
-        .collect();
-
-    Input { rules, updates }
+    let mut rules = Vec::new();
+    let mut updates = Vec::new();
+
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some((a, b)) = line.split_once('|') {
+            if !updates.is_empty() {
+                return Err(InputError::at_line(line_number, "Rule found after updates"));
+            }
+
+            let a = parse_number(line_number, line, a)?;
+            let b = parse_number(line_number, line, b)?;
+            if a < 0 || b < 0 {
+                return Err(InputError::at_line(line_number, "Negative page number"));
+            }
+            rules.push(Rule { a, b });
+        } else {
+            let update = line
+                .split(',')
+                .map(|num| parse_number(line_number, line, num))
+                .collect::<Result<Vec<i32>, _>>()?;
+
+            if update.iter().any(|num| *num < 0) {
+                return Err(InputError::at_line(line_number, "Negative page number"));
+            }
+            updates.push(update);
+        }
+    }
+
+    if updates.is_empty() {
+        return Err(InputError::new("No updates in input"));
+    }
+
+    Ok(Input { rules, updates })
 }
 
 fn is_sorted(update: &[i32], rules: &[Rule], memo: &mut [Option<usize>]) -> bool {
@@ -131,12 +148,12 @@ pub fn solve_part_2(input: &Input) -> i32 {
         update.sort_by(|a, b| {
             if rule_map
                 .get(a)
-                .map_or(false, |b_values| b_values.contains(b))
+                .is_some_and(|b_values| b_values.contains(b))
             {
                 std::cmp::Ordering::Less
             } else if rule_map
                 .get(b)
-                .map_or(false, |a_values| a_values.contains(a))
+                .is_some_and(|a_values| a_values.contains(a))
             {
                 std::cmp::Ordering::Greater
             } else {
@@ -152,44 +169,17 @@ pub fn solve_part_2(input: &Input) -> i32 {
 
 #[cfg(test)]
 mod tests {
-    const TEST_INPUT: &str = r#"47|53
-97|13
-97|61
-97|47
-75|29
-61|13
-75|53
-29|13
-97|29
-53|29
-61|53
-97|53
-61|29
-47|13
-75|47
-97|75
-47|61
-75|61
-47|29
-75|13
-53|13
-
-75,47,61,53,29
-97,61,53,29,13
-75,29,13
-75,97,47,61,53
-61,13,29
-97,13,75,29,47"#;
+    const TEST_INPUT: &str = example!(5, 1);
 
     #[test]
     fn test_part_1() {
-        let input = super::parse(TEST_INPUT);
+        let input = super::parse(TEST_INPUT).unwrap();
         assert_eq!(super::solve_part_1(&input), 143);
     }
 
     #[test]
     fn test_part_2() {
-        let input = super::parse(TEST_INPUT);
+        let input = super::parse(TEST_INPUT).unwrap();
         assert_eq!(super::solve_part_2(&input), 123);
     }
 }
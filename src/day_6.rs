@@ -1,5 +1,8 @@
 use std::collections::HashSet;
 
+use crate::error::InputError;
+use crate::runner::parallel_map;
+use crate::util::map2d::{self, Rgb};
 use crate::util::{Dir, Map2d, Map2dExt, Vec2};
 
 #[derive(Clone, Copy, Debug)]
@@ -41,157 +44,239 @@ impl AsRef<Input> for Input {
     }
 }
 
-pub fn parse(input: &str) -> Input {
-    let raw_map = Map2d::parse_grid(input, |c| match c {
-        '.' => RawTile::Empty,
-        '#' => RawTile::Wall,
-        'v' => RawTile::Guard(Dir::Down),
-        '^' => RawTile::Guard(Dir::Up),
-        '<' => RawTile::Guard(Dir::Left),
-        '>' => RawTile::Guard(Dir::Right),
-        _ => panic!("Invalid character in input"),
-    });
+pub fn parse(input: &str) -> Result<Input, InputError> {
+    let raw_map = Map2d::try_parse_grid(input, |c| match c {
+        '.' => Some(RawTile::Empty),
+        '#' => Some(RawTile::Wall),
+        'v' => Some(RawTile::Guard(Dir::Down)),
+        '^' => Some(RawTile::Guard(Dir::Up)),
+        '<' => Some(RawTile::Guard(Dir::Left)),
+        '>' => Some(RawTile::Guard(Dir::Right)),
+        _ => None,
+    })?;
 
-    let guard_pos =
-        Map2d::find(&raw_map, |tile| matches!(tile, RawTile::Guard(_))).expect("No guard");
+    let guard_pos = Map2d::find(&raw_map, |tile| matches!(tile, RawTile::Guard(_)))
+        .ok_or_else(|| InputError::new("No guard in map"))?;
 
     let guard_dir = match raw_map.get(guard_pos) {
         Some(RawTile::Guard(dir)) => dir,
         _ => unreachable!(),
     };
 
-    Input {
+    Ok(Input {
         tiles: raw_map.convert(),
         guard: Guard {
             pos: guard_pos,
             dir: guard_dir,
         },
-    }
+    })
+}
+
+/// A position on the guard's route, and the guard's state just before they first stepped onto it
+#[derive(Clone, Copy, Debug)]
+struct Visit {
+    pos: Vec2,
+    from: Guard,
 }
 
 enum GuardRoute {
-    Finite { unique_positions: HashSet<Vec2> },
+    /// Each position visited, in order of first visit, starting from the guard's initial
+    /// position, which they arrive at from nowhere
+    Finite {
+        visits: Vec<Visit>,
+    },
     Loop,
 }
 
-fn guard_route(map: &Map2d<Tile>, initial: Guard) -> GuardRoute {
+fn tile_color(tile: Tile) -> Rgb {
+    match tile {
+        Tile::Empty => [16, 16, 32],
+        Tile::Wall => [160, 160, 160],
+    }
+}
+
+/// Walk the guard until they leave the map or loop, recording a frame of each step if `record`
+/// is set and the run is being visualized
+fn guard_route(map: &Map2d<Tile>, initial: Guard, record: bool) -> GuardRoute {
     let mut guard = initial;
     let mut visited_states = HashSet::new(); // Track (position, direction)
-    let mut unique_positions = HashSet::new(); // Track all unique positions visited
+    let mut visited = Map2d::new_default(map.size, false); // Track all unique positions visited
+    let mut visits = Vec::new();
+
+    let record = record && map2d::is_recording();
+    let mut path = Vec::new();
 
+    let mut from = initial;
     loop {
         // Record the current state (position and direction)
         if !visited_states.insert((guard.pos, guard.dir)) {
             return GuardRoute::Loop; // Detected a loop
         }
 
-        // Record the unique position visited
-        unique_positions.insert(guard.pos);
+        // Record the first visit to each position
+        let seen = visited.get_mut(guard.pos).unwrap();
+        if !*seen {
+            *seen = true;
+            visits.push(Visit {
+                pos: guard.pos,
+                from,
+            });
+        }
+
+        if record {
+            path.push(guard.pos);
+            map2d::record_frame(|| {
+                map.frame(tile_color)
+                    .path(path.iter().copied(), [220, 180, 40])
+                    .point(guard.pos, [255, 40, 40])
+            });
+        }
 
         // Determine the next position based on the guard's direction
         let next_pos = guard.pos + guard.dir;
 
         match map.get(next_pos) {
             Some(Tile::Empty) => {
+                from = guard;
                 guard.pos = next_pos;
             }
             Some(Tile::Wall) => {
                 guard.dir = guard.dir.rotate_right();
             }
             None => {
-                return GuardRoute::Finite { unique_positions };
+                return GuardRoute::Finite { visits };
             }
         }
     }
 }
 
-pub fn solve_part_1(input: &Input) -> usize {
-    let route = guard_route(&input.tiles, input.guard);
-    let GuardRoute::Finite { unique_positions } = route else {
-        panic!("Infinite loop in initial input")
+pub fn solve_part_1(input: &Input) -> Result<usize, InputError> {
+    let route = guard_route(&input.tiles, input.guard, true);
+    let GuardRoute::Finite { visits } = route else {
+        return Err(InputError::new("Guard loops forever in initial map"));
     };
-    unique_positions.len()
+    Ok(visits.len())
 }
 
-pub fn solve_part_2(input: &Input) -> u64 {
-    let GuardRoute::Finite {
-        unique_positions: mut initial_route,
-    } = guard_route(&input.tiles, input.guard)
-    else {
-        unreachable!()
-    };
-    
-    // Remove the guard's starting position from the initial route
-    initial_route.remove(&input.guard.pos);
+/// For each direction, where the guard stops when walking that way from each position, just
+/// before the next wall, or `None` if they'd walk off the map
+struct JumpTable {
+    stops: [Map2d<Option<Vec2>>; 4],
+}
 
-    let mut possible_positions = 0;
-    let mut test_map = input.tiles.clone();
+impl JumpTable {
+    fn new(map: &Map2d<Tile>) -> Self {
+        let stops = Dir::ALL.map(|dir| {
+            let mut stops = Map2d::new_default(map.size, None);
 
-    // Loop over all positions in the map
-    for pos in initial_route {
-        // Temporarily place a wall at this position
-        *test_map.get_mut(pos).unwrap() = Tile::Wall;
+            // Fill in each row or column starting from the edge being walked towards, so the
+            // next position along is always already known
+            let mut order = (0..map.data.len())
+                .map(|i| map.pos_of(i))
+                .collect::<Vec<_>>();
+            order.sort_by_key(|pos| -pos.dot(dir.to_vec2()));
 
-        // Simulate guard's movement with the obstruction
-        let mut guard = input.guard;
-        let mut visited_states = HashSet::new();
-        let mut is_loop = false;
+            for pos in order {
+                let next = pos + dir;
+                let stop = match map.get(next) {
+                    None => None,
+                    Some(Tile::Wall) => Some(pos),
+                    Some(Tile::Empty) => stops.get(next).unwrap(),
+                };
+                *stops.get_mut(pos).unwrap() = stop;
+            }
+
+            stops
+        });
+
+        Self { stops }
+    }
 
+    /// Where the guard stops when walking straight ahead, with an extra wall at `obstacle`
+    fn stop(&self, guard: Guard, obstacle: Vec2) -> Option<Vec2> {
+        let stop = self.stops[guard.dir.index()].get(guard.pos).unwrap();
+
+        let dir = guard.dir.to_vec2();
+        let to_obstacle = obstacle - guard.pos;
+        let ahead = to_obstacle.dot(dir);
+        let in_line = to_obstacle == dir * ahead;
+        let blocked =
+            in_line && ahead > 0 && stop.is_none_or(|stop| ahead <= (stop - guard.pos).dot(dir));
+
+        if blocked {
+            Some(obstacle - dir)
+        } else {
+            stop
+        }
+    }
+
+    /// Whether the guard walks in a loop from `from`, with an extra wall at `obstacle`
+    fn loops_with(&self, from: Guard, obstacle: Vec2) -> bool {
+        let mut guard = from;
+        let mut turns = HashSet::new();
         loop {
-            // Check if the current state has been visited before
-            if !visited_states.insert((guard.pos, guard.dir)) {
-                is_loop = true;
-                break;
-            }
+            let Some(stop) = self.stop(guard, obstacle) else {
+                return false;
+            };
 
-            let next_pos = guard.pos + guard.dir;
-            match test_map.get(next_pos) {
-                Some(Tile::Empty) => {
-                    guard.pos = next_pos;
-                }
-                Some(Tile::Wall) => {
-                    guard.dir = guard.dir.rotate_right();
-                }
-                None => break,
+            guard.pos = stop;
+            guard.dir = guard.dir.rotate_right();
+            if !turns.insert((guard.pos, guard.dir)) {
+                return true;
             }
         }
+    }
+}
 
-        // If a loop is found, count this obstruction position as valid
-        if is_loop {
-            possible_positions += 1;
-        }
+/// The number of positions where a new obstacle would trap the guard in a loop, checking them
+/// on `jobs` worker threads
+fn count_loops(input: &Input, jobs: usize) -> Result<u64, InputError> {
+    let GuardRoute::Finite { visits } = guard_route(&input.tiles, input.guard, false) else {
+        return Err(InputError::new("Guard loops forever in initial map"));
+    };
 
-        // Reset the test map
-        *test_map.get_mut(pos).unwrap() = Tile::Empty;
-    }
+    let jumps = JumpTable::new(&input.tiles);
 
-    possible_positions
+    // An obstacle only changes the route from the first time the guard would have walked into
+    // it, so each check starts from there. The guard's starting position can't be obstructed.
+    let loops = parallel_map(&visits[1..], jobs, |visit| {
+        jumps.loops_with(visit.from, visit.pos)
+    });
+
+    Ok(loops.into_iter().filter(|is_loop| *is_loop).count() as u64)
+}
+
+pub fn solve_part_2(input: &Input) -> Result<u64, InputError> {
+    let jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+    count_loops(input, jobs)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"....#.....
-.........#
-..........
-..#.......
-.......#..
-..........
-.#..^.....
-........#.
-#.........
-......#..."#;
+    const TEST_INPUT: &str = example!(6, 1);
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
-        assert_eq!(solve_part_1(&input), 41);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(solve_part_1(&input), Ok(41));
     }
 
     #[test]
     fn test_part_2() {
-        let input = parse(TEST_INPUT);
-        assert_eq!(solve_part_2(&input), 6);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(solve_part_2(&input), Ok(6));
+        assert_eq!(count_loops(&input, 1), Ok(6));
+        assert_eq!(count_loops(&input, 3), Ok(6));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("..#\n.x^\n...").err(),
+            Some(InputError::at(2, 2, "Unexpected character 'x'"))
+        );
+        assert_eq!(parse("..#\n...").err(), Some(InputError::new("No guard in map")));
     }
 }
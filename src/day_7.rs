@@ -1,135 +1,163 @@
+use crate::error::{parse_number, InputError};
+
 #[derive(Clone, Debug)]
 pub struct Line {
     target: i64,
     numbers: Vec<i64>,
 }
 
-pub fn parse(input: &str) -> Vec<Line> {
+pub fn parse(input: &str) -> Result<Vec<Line>, InputError> {
     // Lines in the form: "<target>: <number> <number> ... <number>"
 
     let mut lines = Vec::new();
 
-    for line in input.lines() {
-        let (target, numbers) = line.split_once(':').unwrap();
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        let (target, numbers) = line
+            .split_once(':')
+            .ok_or_else(|| InputError::at_line(line_number, "Missing ':'"))?;
 
-        let target = target.parse().unwrap();
+        let target = parse_number(line_number, line, target)?;
         let numbers = numbers
             .split_whitespace()
-            .map(str::parse)
-            .map(Result::unwrap)
-            .collect();
+            .map(|num| parse_number(line_number, line, num))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if numbers.is_empty() {
+            return Err(InputError::at_line(line_number, "No numbers after ':'"));
+        }
 
         lines.push(Line { target, numbers });
     }
 
-    lines
+    Ok(lines)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Op {
     Add,
     Mul,
+    Concat,
 }
 
-struct Opiter {
-    ops: Vec<Op>,
-    done: bool,
+/// The smallest power of ten greater than `x`, i.e. what to multiply by to append `x`'s digits
+fn digit_shift(x: i64) -> i64 {
+    let mut shift = 10;
+    while shift <= x {
+        shift *= 10;
+    }
+    shift
 }
 
-impl Opiter {
-    fn new(n: usize) -> Self {
-        Self {
-            ops: vec![Op::Add; n],
-            done: false,
+impl Op {
+    #[cfg(test)]
+    fn apply(self, lhs: i64, rhs: i64) -> i64 {
+        match self {
+            Op::Add => lhs + rhs,
+            Op::Mul => lhs * rhs,
+            Op::Concat => lhs * digit_shift(rhs) + rhs,
         }
     }
 
-    fn next(&mut self) -> Option<&[Op]> {
-        if self.done {
-            return None;
-        }
-
-        if self.ops.iter().all(|o| *o == Op::Mul) {
-            self.done = true;
-        }
-
-        for op in self.ops.iter_mut() {
-            if *op != Op::Mul {
-                *op = Op::Mul;
-                break;
-            } else {
-                *op = Op::Add;
+    /// The left hand side that would give `result` when this op is applied to it and `rhs`, if
+    /// there is one
+    fn unapply(self, result: i64, rhs: i64) -> Option<i64> {
+        match self {
+            Op::Add => (result >= rhs).then(|| result - rhs),
+            Op::Mul => (rhs != 0 && result % rhs == 0).then(|| result / rhs),
+            Op::Concat => {
+                let shift = digit_shift(rhs);
+                (result % shift == rhs).then(|| result / shift)
             }
         }
-
-        Some(&self.ops)
     }
 }
 
-fn eval(numbers: &[i64], ops: &[Op]) -> i64 {
-    debug_assert_eq!(numbers.len(), ops.len() + 1);
-    debug_assert!(!numbers.is_empty());
-
-    let mut working = numbers[0];
+/// Whether some choice of `ops` between `numbers`, evaluated left to right, gives `target`
+///
+/// Works backwards from the last number, undoing each op in turn, so a whole branch of the search
+/// is dropped as soon as the remaining target isn't divisible by, at least, or suffixed by the
+/// next number.
+fn solvable(target: i64, numbers: &[i64], ops: &[Op]) -> bool {
+    let Some((&last, rest)) = numbers.split_last() else {
+        return false;
+    };
+
+    if rest.is_empty() {
+        return target == last;
+    }
 
-    for (num, op) in numbers[1..].iter().zip(ops.iter()) {
-        working = match op {
-            Op::Add => working + num,
-            Op::Mul => working * num,
-        }
+    // Anything times zero is zero, so whatever the rest of the numbers come to will do
+    if target == 0 && last == 0 && ops.contains(&Op::Mul) {
+        return true;
     }
 
-    working
+    ops.iter().any(|op| {
+        op.unapply(target, last)
+            .is_some_and(|lhs| solvable(lhs, rest, ops))
+    })
 }
 
-pub fn solve_part_1(input: &[Line]) -> i64 {
-    let mut sum = 0;
-    for line in input {
-        let mut op_it = Opiter::new(line.numbers.len() - 1);
-
-        while let Some(ops) = op_it.next() {
-            if eval(&line.numbers, ops) == line.target {
-                sum += line.target;
-                break;
-            }
-        }
-    }
+fn total_solvable(input: &[Line], ops: &[Op]) -> i64 {
+    input
+        .iter()
+        .filter(|line| solvable(line.target, &line.numbers, ops))
+        .map(|line| line.target)
+        .sum()
+}
 
-    sum
+pub fn solve_part_1(input: &[Line]) -> i64 {
+    total_solvable(input, &[Op::Add, Op::Mul])
 }
 
-pub fn solve_part_2(input: &[Line]) -> u64 {
-    0
+pub fn solve_part_2(input: &[Line]) -> i64 {
+    total_solvable(input, &[Op::Add, Op::Mul, Op::Concat])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"190: 10 19
-3267: 81 40 27
-83: 17 5
-156: 15 6
-7290: 6 8 6 15
-161011: 16 10 13
-192: 17 8 14
-21037: 9 7 18 13
-292: 11 6 16 20"#;
+    const TEST_INPUT: &str = example!(7, 1);
 
     #[test]
-    fn test_op_iter() {
-        let mut i = Opiter::new(3);
-        let all = std::iter::from_fn(move || i.next().map(|slice| slice.to_vec()))
-            .collect::<Vec<Vec<Op>>>();
-
-        assert_eq!(all.len(), 8);
-        assert_eq!(all[0], [Op::Mul, Op::Add, Op::Add]);
-        assert_eq!(all[7], [Op::Add, Op::Add, Op::Add]);
+    fn test_unapply() {
+        for op in [Op::Add, Op::Mul, Op::Concat] {
+            for (lhs, rhs) in [(15, 6), (1, 10), (7, 100), (48, 9), (0, 56)] {
+                let result = op.apply(lhs, rhs);
+                assert_eq!(op.unapply(result, rhs), Some(lhs), "{op:?} {lhs} {rhs}");
+            }
+        }
+
+        assert_eq!(Op::Mul.unapply(190, 19), Some(10));
+        assert_eq!(Op::Mul.unapply(191, 19), None);
+        assert_eq!(Op::Add.unapply(5, 6), None);
+        assert_eq!(Op::Concat.unapply(156, 56), Some(1));
+        assert_eq!(Op::Concat.unapply(156, 6), Some(15));
+        assert_eq!(Op::Concat.unapply(156, 7), None);
+        assert_eq!(Op::Concat.unapply(56, 56), Some(0));
+        assert_eq!(Op::Concat.unapply(6, 56), None);
+    }
+
+    #[test]
+    fn test_solvable_zeros() {
+        let ops = [Op::Add, Op::Mul];
+        assert!(solvable(0, &[5, 0], &ops));
+        assert!(solvable(0, &[3, 4, 0], &ops));
+        assert!(!solvable(0, &[3, 4, 0], &[Op::Add]));
+        assert!(solvable(4, &[3, 0, 4], &ops));
+        assert!(!solvable(5, &[3, 0, 4], &[Op::Mul]));
     }
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&input), 3749);
     }
+
+    #[test]
+    fn test_part_2() {
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(solve_part_2(&input), 11387);
+    }
 }
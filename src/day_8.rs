@@ -1,6 +1,7 @@
 use std::{collections::HashSet, convert::identity};
 
-use crate::util::{combinatorial, Map2d, Map2dExt, Vec2};
+use crate::error::InputError;
+use crate::util::{combinatorial, Map2d, Map2dExt, UnboundedLine2, Vec2};
 
 pub struct Input {
     map_size: Vec2,
@@ -15,8 +16,8 @@ impl AsRef<Input> for Input {
     }
 }
 
-pub fn parse(input: &str) -> Input {
-    let map = Map2d::parse_grid(input, identity);
+pub fn parse(input: &str) -> Result<Input, InputError> {
+    let map = Map2d::parse_grid(input, identity)?;
 
     let mut antennas = Vec::new();
     for y in 0..map.size.y {
@@ -32,68 +33,66 @@ pub fn parse(input: &str) -> Input {
 
     antennas.sort_by_key(|(c, _pos)| *c);
 
-    Input {
+    Ok(Input {
         map_size: map.size,
         antennas,
-    }
+    })
+}
+
+/// The line through each pair of antennas with the same frequency, starting from the first of
+/// the pair, and how many steps along it the second is
+fn antenna_lines(input: &Input) -> impl Iterator<Item = (UnboundedLine2, i64)> + '_ {
+    input
+        .antennas
+        .chunk_by(|(char_a, _), (char_b, _)| char_a == char_b)
+        .flat_map(combinatorial::pairs)
+        .map(|((_, pos_a), (_, pos_b))| {
+            let line = UnboundedLine2::through(*pos_a, *pos_b).expect("Antennas are distinct");
+            let steps = line.steps_to(*pos_b).unwrap();
+            (line, steps)
+        })
 }
 
 pub fn solve_part_1(input: &Input) -> usize {
     let mut antinodes = HashSet::new();
 
-    let mut idx = 0;
-    while idx < input.antennas.len() {
-        let this_c = input.antennas[idx].0;
-        let len = input.antennas[idx..]
-            .iter()
-            .position(|(c, _pos)| *c != this_c)
-            .unwrap_or(input.antennas[idx..].len());
-
-        let this_slice = &input.antennas[idx..(idx + len)];
-
-        for ((char_a, pos_a), (char_b, pos_b)) in combinatorial::pairs(this_slice) {
-            debug_assert_eq!(char_a, char_b);
-            
-            // The vector from a to b
-            let ab = pos_b - pos_a;
-            
-            for test in [*pos_a - ab, *pos_b + ab] {
-                if test.inside_map(input.map_size) {
-                    antinodes.insert(test);
-                }
+    for (line, steps) in antenna_lines(input) {
+        // Antinodes are twice as far from one antenna as the other, on the far side of each
+        for test in [line.at(-steps), line.at(2 * steps)] {
+            if test.inside_map(input.map_size) {
+                antinodes.insert(test);
             }
         }
-
-        idx += this_slice.len();
     }
 
     antinodes.len()
 }
 
-pub fn solve_part_2(input: &Input) -> u64 {
-    0
+pub fn solve_part_2(input: &Input) -> usize {
+    let mut antinodes = HashSet::new();
+
+    for (line, _steps) in antenna_lines(input) {
+        antinodes.extend(line.rasterize(input.map_size));
+    }
+
+    antinodes.len()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = r#"............
-........0...
-.....0......
-.......0....
-....0.......
-......A.....
-............
-............
-........A...
-.........A..
-............
-............"#;
+    const TEST_INPUT: &str = example!(8, 1);
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&input), 14);
     }
+
+    #[test]
+    fn test_part_2() {
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(solve_part_2(&input), 34);
+    }
 }
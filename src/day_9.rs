@@ -1,84 +1,255 @@
-#[derive(Clone, Copy, Debug)]
-pub enum Contents {
-    Free,
-    File { id: u64 },
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::{Display, Formatter};
+
+use crate::error::InputError;
+
+/// A file occupying `len` contiguous blocks from `offset`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileSpan {
+    id: u64,
+    offset: u64,
+    len: u64,
+}
+
+impl FileSpan {
+    fn end(&self) -> u64 {
+        self.offset + self.len
+    }
+
+    fn checksum(&self) -> u64 {
+        // id * (offset + (offset + 1) + ... + (offset + len - 1))
+        self.id * (self.len * self.offset + self.len * self.len.saturating_sub(1) / 2)
+    }
 }
 
-pub fn parse(input: &str) -> Vec<Contents> {
-    let mut cell_lengths = input
-        .chars()
-        .filter_map(|c| c.to_digit(10))
-        .map(|d| d as u64);
-
-    let mut id = 0;
-    let mut contents = Contents::File { id };
-    let mut output = Vec::new();
-    for len in cell_lengths {
-        for _ in 0..len {
-            output.push(contents);
+/// A run of free blocks between two files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Gap {
+    offset: u64,
+    len: u64,
+}
+
+/// The disk as a list of file spans and the gaps between them, both in disk order
+///
+/// Displays in the puzzle's notation, e.g. `00...111...2`, with ids past 9 shown by their last
+/// digit.
+#[derive(Clone, Debug)]
+pub struct Disk {
+    files: Vec<FileSpan>,
+    gaps: Vec<Gap>,
+    len: u64,
+}
+
+impl Disk {
+    /// Lay out `files` on a disk of `len` blocks, finding the gaps between them
+    fn from_files(mut files: Vec<FileSpan>, len: u64) -> Self {
+        files.retain(|file| file.len > 0);
+        files.sort_by_key(|file| file.offset);
+
+        let mut gaps = Vec::new();
+        let mut end = 0;
+        for file in files.iter() {
+            if file.offset > end {
+                gaps.push(Gap {
+                    offset: end,
+                    len: file.offset - end,
+                });
+            }
+            end = file.end();
         }
+        if len > end {
+            gaps.push(Gap {
+                offset: end,
+                len: len - end,
+            });
+        }
+
+        Self { files, gaps, len }
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.files.iter().map(FileSpan::checksum).sum()
+    }
+
+    /// Move file blocks one at a time from the end of the disk into the leftmost free block,
+    /// until there are no gaps between files
+    pub fn compact_blocks(&self) -> Disk {
+        let mut remaining = self.files.clone();
+        let mut moved = Vec::new();
+
+        'gaps: for gap in self.gaps.iter() {
+            let mut gap = *gap;
+            while gap.len > 0 {
+                let Some(last) = remaining.last_mut() else {
+                    break 'gaps;
+                };
+                if last.offset < gap.offset {
+                    break 'gaps;
+                }
 
-        contents = match contents {
-            Contents::Free => {
-                id += 1;
-                Contents::File { id }
+                let len = std::cmp::min(gap.len, last.len);
+                moved.push(FileSpan {
+                    id: last.id,
+                    offset: gap.offset,
+                    len,
+                });
+
+                gap.offset += len;
+                gap.len -= len;
+                last.len -= len;
+                if last.len == 0 {
+                    remaining.pop();
+                }
             }
-            Contents::File { .. } => Contents::Free,
-        };
+        }
+
+        remaining.extend(moved);
+        Disk::from_files(remaining, self.len)
     }
 
-    output
-}
+    /// Move each whole file once, in decreasing order of id, into the leftmost gap that fits it
+    /// if that's further left than the file already is
+    pub fn compact_files(&self) -> Disk {
+        // The offsets and lengths of the gaps of each length, leftmost first. File lengths are
+        // single digits, so every gap longer than 9 shares the last bucket, keeping its real
+        // length for whatever's left of it once a file has moved in.
+        const MAX_LEN: usize = 9;
+        let bucket = |len: u64| std::cmp::min(len as usize, MAX_LEN);
+        let mut free: [BinaryHeap<Reverse<(u64, u64)>>; MAX_LEN + 1] = Default::default();
+        for gap in self.gaps.iter() {
+            free[bucket(gap.len)].push(Reverse((gap.offset, gap.len)));
+        }
 
-pub fn solve_part_1(input: &[Contents]) -> u64 {
-    let mut disk = input.to_vec();
+        let mut files = self.files.clone();
+        files.sort_by_key(|file| Reverse(file.id));
 
-    // Two pointers, one to the leftmost free cell, and one to the rightmost file cell
-    // Each iteration, swap their contents and update the pointers
+        for file in files.iter_mut() {
+            let Some((gap_bucket, Reverse((gap_offset, gap_len)))) = (bucket(file.len)..=MAX_LEN)
+                .filter_map(|len| free[len].peek().map(|gap| (len, *gap)))
+                .min_by_key(|(_, Reverse((offset, _)))| *offset)
+            else {
+                continue;
+            };
 
-    let mut min_free = disk
-        .iter()
-        .position(|c| matches!(c, Contents::Free))
-        .unwrap();
-    let mut max_file = disk
-        .iter()
-        .rposition(|c| matches!(c, Contents::File { .. }))
-        .unwrap();
+            if gap_offset >= file.offset {
+                continue;
+            }
 
-    while min_free < max_file {
-        disk.swap(min_free, max_file);
+            free[gap_bucket].pop();
+            file.offset = gap_offset;
 
-        while let Contents::File { .. } = disk[min_free] {
-            min_free += 1;
+            // The space this file leaves behind is right of every file still to be moved, so
+            // it's never needed again, but the rest of the gap might be
+            let rest = gap_len - file.len;
+            if rest > 0 {
+                free[bucket(rest)].push(Reverse((gap_offset + file.len, rest)));
+            }
         }
 
-        while let Contents::Free = disk[max_file] {
-            max_file -= 1;
+        Disk::from_files(files, self.len)
+    }
+}
+
+impl Display for Disk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut end = 0;
+        for file in self.files.iter() {
+            write!(f, "{}", ".".repeat((file.offset - end) as usize))?;
+            let digit = char::from_digit((file.id % 10) as u32, 10).unwrap();
+            write!(f, "{}", digit.to_string().repeat(file.len as usize))?;
+            end = file.end();
         }
+        write!(f, "{}", ".".repeat(self.len.saturating_sub(end) as usize))
     }
+}
 
-    disk.iter()
-        .enumerate()
-        .map(|(idx, cell)| match cell {
-            Contents::File { id } => idx as u64 * *id,
-            Contents::Free => 0,
-        })
-        .sum()
+impl AsRef<Disk> for Disk {
+    fn as_ref(&self) -> &Disk {
+        self
+    }
 }
 
-pub fn solve_part_2(input: &[Contents]) -> u64 {
-    0
+pub fn parse(input: &str) -> Result<Disk, InputError> {
+    let mut files = Vec::with_capacity(input.len() / 2 + 1);
+    let mut offset = 0;
+    let mut is_file = true;
+    for (idx, line) in input.lines().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            let len = c.to_digit(10).ok_or_else(|| {
+                InputError::at(idx + 1, col + 1, format!("Expected a digit, found {c:?}"))
+            })? as u64;
+
+            if is_file {
+                files.push(FileSpan {
+                    id: files.len() as u64,
+                    offset,
+                    len,
+                });
+            }
+            offset += len;
+            is_file = !is_file;
+        }
+    }
+
+    if !files.iter().any(|file| file.len > 0) {
+        return Err(InputError::new("Disk contains no files"));
+    }
+
+    Ok(Disk::from_files(files, offset))
+}
+
+pub fn solve_part_1(input: &Disk) -> u64 {
+    input.compact_blocks().checksum()
+}
+
+pub fn solve_part_2(input: &Disk) -> u64 {
+    input.compact_files().checksum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const TEST_INPUT: &str = "2333133121414131402";
+    const TEST_INPUT: &str = example!(9, 1);
 
     #[test]
     fn test_part_1() {
-        let input = parse(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(solve_part_1(&input), 1928);
     }
+
+    #[test]
+    fn test_part_2() {
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(solve_part_2(&input), 2858);
+
+        // The zero length file between the two gaps leaves a single gap longer than 9
+        let input = parse("1901901").unwrap();
+        assert_eq!(input.to_string(), "0..........2222222223");
+        assert_eq!(solve_part_2(&input), 111);
+    }
+
+    #[test]
+    fn test_render() {
+        let disk = parse(TEST_INPUT).unwrap();
+        assert_eq!(
+            disk.to_string(),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+        assert_eq!(
+            disk.compact_blocks().to_string(),
+            "0099811188827773336446555566.............."
+        );
+        assert_eq!(
+            disk.compact_files().to_string(),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+
+        assert_eq!(parse("12345").unwrap().to_string(), "0..111....22222");
+        assert_eq!(
+            parse("00").err(),
+            Some(InputError::new("Disk contains no files"))
+        );
+    }
 }
@@ -1,11 +1,13 @@
-pub fn parse(input: &str) -> String {
-    input.to_string()
+use crate::error::InputError;
+
+pub fn parse(input: &str) -> Result<String, InputError> {
+    Ok(input.to_string())
 }
 
-pub fn solve_part_1(input: &str) -> u64 {
+pub fn solve_part_1(_input: &str) -> u64 {
     0
 }
 
-pub fn solve_part_2(input: &str) -> u64 {
+pub fn solve_part_2(_input: &str) -> u64 {
     0
-}
\ No newline at end of file
+}
@@ -0,0 +1,205 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// A problem with a day's puzzle input, located as precisely as possible
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputError {
+    /// One-based line number
+    pub line: Option<usize>,
+
+    /// One-based column number, counted in chars
+    pub column: Option<usize>,
+
+    pub message: String,
+}
+
+impl InputError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at_line(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            ..Self::new(message)
+        }
+    }
+
+    pub fn at(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            column: Some(column),
+            ..Self::new(message)
+        }
+    }
+
+    /// An error about `part`, which must be a subslice of `line`
+    pub fn in_line(line_number: usize, line: &str, part: &str, message: impl Into<String>) -> Self {
+        let offset = (part.as_ptr() as usize).saturating_sub(line.as_ptr() as usize);
+        debug_assert!(offset <= line.len(), "part is not a subslice of line");
+
+        let column = line[..offset.min(line.len())].chars().count() + 1;
+        Self::at(line_number, column, message)
+    }
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "line {line}, column {column}: ")?,
+            (Some(line), None) => write!(f, "line {line}: ")?,
+            _ => (),
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// Parse a number from `token`, a subslice of `line`, reporting its location on failure
+pub fn parse_number<T: std::str::FromStr>(
+    line_number: usize,
+    line: &str,
+    token: &str,
+) -> Result<T, InputError> {
+    token.parse().map_err(|_| {
+        InputError::in_line(
+            line_number,
+            line,
+            token,
+            format!("Invalid number {token:?}"),
+        )
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Phase::Parse => "parse",
+            Phase::Part1 => "part 1",
+            Phase::Part2 => "part 2",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DayErrorKind {
+    /// The day rejected its input
+    Input(InputError),
+    Panic(String),
+    Timeout(Duration),
+}
+
+/// An error from a single phase of running a day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayError {
+    pub day: u8,
+    pub phase: Phase,
+    pub kind: DayErrorKind,
+}
+
+impl DayError {
+    /// Short label for the error shown in place of an answer
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            DayErrorKind::Input(_) => "ERROR",
+            DayErrorKind::Panic(_) => "PANIC",
+            DayErrorKind::Timeout(_) => "TIMEOUT",
+        }
+    }
+}
+
+impl Display for DayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Day {:02} {}: ", self.day, self.phase)?;
+        match &self.kind {
+            DayErrorKind::Input(err) => write!(f, "{}", err),
+            DayErrorKind::Panic(message) => write!(f, "panicked: {}", message),
+            DayErrorKind::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+        }
+    }
+}
+
+impl std::error::Error for DayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            DayErrorKind::Input(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result type of a day's part functions, converted into the answer shown in the results
+pub trait IntoAnswer {
+    fn into_answer(self) -> Result<String, InputError>;
+}
+
+macro_rules! impl_into_answer {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl IntoAnswer for $ty {
+                fn into_answer(self) -> Result<String, InputError> {
+                    Ok(self.to_string())
+                }
+            }
+        )*
+    }
+}
+
+impl_into_answer!(i32, i64, u32, u64, usize, String);
+
+impl<T: IntoAnswer> IntoAnswer for Result<T, InputError> {
+    fn into_answer(self) -> Result<String, InputError> {
+        self.and_then(IntoAnswer::into_answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_line() {
+        let line = "p=0,4 v=3,x";
+        let err = InputError::in_line(7, line, &line[10..], "Bad");
+        assert_eq!(err, InputError::at(7, 11, "Bad"));
+        assert_eq!(err.to_string(), "line 7, column 11: Bad");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let line = "3   x4";
+        assert_eq!(parse_number::<u64>(1, line, &line[..1]), Ok(3));
+        assert_eq!(
+            parse_number::<u64>(1, line, &line[4..])
+                .unwrap_err()
+                .to_string(),
+            "line 1, column 5: Invalid number \"x4\""
+        );
+    }
+
+    #[test]
+    fn test_into_answer() {
+        assert_eq!(5u64.into_answer(), Ok("5".to_string()));
+        assert_eq!(
+            Ok::<i64, InputError>(-5).into_answer(),
+            Ok("-5".to_string())
+        );
+        assert_eq!(
+            Err::<i64, _>(InputError::new("Oops")).into_answer(),
+            Err(InputError::new("Oops"))
+        );
+    }
+}
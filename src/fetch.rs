@@ -0,0 +1,503 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const DEFAULT_YEAR: u16 = 2024;
+
+/// Environment variable holding the adventofcode.com session cookie
+pub const SESSION_COOKIE_VAR: &str = "AOC_SESSION_COOKIE";
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// A request needed to be made, but no session cookie was configured
+    MissingSession,
+
+    /// The server rejected the session cookie
+    Unauthorized {
+        url: String,
+    },
+
+    /// The puzzle hasn't unlocked yet, or doesn't exist
+    NotUnlocked {
+        url: String,
+    },
+
+    /// The server responded with an unexpected status, after any retries
+    Status {
+        url: String,
+        status: u16,
+    },
+
+    /// The server responded successfully, but with something that doesn't look like the
+    /// requested content, e.g. an HTML error page in place of a puzzle input
+    UnexpectedBody {
+        url: String,
+        reason: &'static str,
+    },
+
+    Http(reqwest::Error),
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+}
+
+impl Display for FetchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::MissingSession => {
+                write!(f, "Input not cached, and {} not set", SESSION_COOKIE_VAR)
+            }
+            FetchError::Unauthorized { url } => {
+                write!(f, "Session cookie rejected when fetching {}", url)
+            }
+            FetchError::NotUnlocked { url } => write!(f, "{} isn't unlocked yet", url),
+            FetchError::Status { url, status } => {
+                write!(f, "Unexpected status {} when fetching {}", status, url)
+            }
+            FetchError::UnexpectedBody { url, reason } => {
+                write!(f, "Unexpected response from {}: {}", url, reason)
+            }
+            // The underlying errors are reported as the source
+            FetchError::Http(_) => write!(f, "HTTP request failed"),
+            FetchError::Io { path, .. } => write!(f, "Failed to access {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FetchError::Http(err) => Some(err),
+            FetchError::Io { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(err: reqwest::Error) -> Self {
+        FetchError::Http(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    /// Scheme and host to fetch from, without a trailing slash
+    pub base_url: String,
+    pub year: u16,
+
+    /// Value of the `session` cookie, required for anything specific to a user
+    pub session: Option<String>,
+
+    /// Minimum time between the start of consecutive requests
+    pub min_interval: Duration,
+
+    /// Number of times to retry a request that failed with a connection error or a server error
+    pub retries: u32,
+
+    /// Delay before the first retry, doubling for each retry after that
+    pub retry_delay: Duration,
+}
+
+impl FetchConfig {
+    /// The default config, taking the session cookie from `AOC_SESSION_COOKIE` if it's set
+    pub fn from_env() -> Self {
+        Self {
+            session: std::env::var(SESSION_COOKIE_VAR).ok(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            year: DEFAULT_YEAR,
+            session: None,
+            min_interval: Duration::from_secs(1),
+            retries: 2,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Fetches puzzle inputs, rate limiting requests across every thread sharing the fetcher
+pub struct Fetcher {
+    config: FetchConfig,
+    client: reqwest::blocking::Client,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl Fetcher {
+    pub fn new(config: FetchConfig) -> Result<Self, FetchError> {
+        let client = reqwest::blocking::ClientBuilder::default()
+            .user_agent("github/joey9801")
+            .build()?;
+
+        Ok(Self {
+            config,
+            client,
+            last_request: Mutex::new(None),
+        })
+    }
+
+    pub fn config(&self) -> &FetchConfig {
+        &self.config
+    }
+
+    /// Full URL of a page for this fetcher's year, e.g. `day/5/input`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}/{}", self.config.base_url, self.config.year, path)
+    }
+
+    /// Block until a request may be made without breaking the rate limit
+    fn wait_for_slot(&self) {
+        let mut last_request = self.last_request.lock().unwrap();
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.config.min_interval {
+                std::thread::sleep(self.config.min_interval - elapsed);
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+
+    /// Send a request built by `build`, retrying connection errors and server errors
+    ///
+    /// Returns the body of the first successful response.
+    pub(crate) fn send(
+        &self,
+        url: &str,
+        build: impl Fn(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<String, FetchError> {
+        self.send_with_retries(url, self.config.retries, build)
+    }
+
+    /// As `send`, but with the given number of retries in place of the configured number
+    pub(crate) fn send_with_retries(
+        &self,
+        url: &str,
+        retries: u32,
+        build: impl Fn(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<String, FetchError> {
+        let session = self
+            .config
+            .session
+            .as_deref()
+            .ok_or(FetchError::MissingSession)?;
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_slot();
+
+            let response = build(&self.client)
+                .header(reqwest::header::COOKIE, format!("session={session}"))
+                .send();
+
+            let retryable = match response {
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text()?;
+
+                    if status.is_success() {
+                        return Ok(body);
+                    }
+
+                    let url = url.to_string();
+                    if body.contains("log in") {
+                        return Err(FetchError::Unauthorized { url });
+                    }
+                    if status == reqwest::StatusCode::NOT_FOUND {
+                        return Err(FetchError::NotUnlocked { url });
+                    }
+
+                    let err = FetchError::Status {
+                        url,
+                        status: status.as_u16(),
+                    };
+                    if !(status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+                    {
+                        return Err(err);
+                    }
+                    err
+                }
+                Err(err) if err.is_connect() || err.is_timeout() => err.into(),
+                Err(err) => return Err(err.into()),
+            };
+
+            if attempt >= retries {
+                return Err(retryable);
+            }
+            std::thread::sleep(self.config.retry_delay * 2u32.pow(attempt));
+            attempt += 1;
+        }
+    }
+
+    /// Fetch a day's puzzle input, checking that it looks like an input before returning it
+    pub fn input(&self, day: u8) -> Result<String, FetchError> {
+        let url = self.url(&format!("day/{day}/input"));
+        let body = self.send(&url, |client| client.get(&url))?;
+
+        let reason = if body.trim().is_empty() {
+            Some("empty input")
+        } else if body.trim_start().starts_with('<') {
+            Some("got HTML in place of an input")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => Err(FetchError::UnexpectedBody { url, reason }),
+            None => Ok(body),
+        }
+    }
+
+    /// Fetch a day's puzzle description page, including part 2 once part 1 has been solved
+    pub fn puzzle_page(&self, day: u8) -> Result<String, FetchError> {
+        let url = self.url(&format!("day/{day}"));
+        let body = self.send(&url, |client| client.get(&url))?;
+
+        if !body.contains("<article") {
+            return Err(FetchError::UnexpectedBody {
+                url,
+                reason: "no puzzle description in the page",
+            });
+        }
+        Ok(body)
+    }
+
+    /// A day's puzzle input from `input_root`, fetching and caching it there if it isn't already
+    pub fn cached_input(&self, input_root: &Path, day: u8) -> Result<String, FetchError> {
+        self.cached_input_with(input_root, day, || {
+            eprintln!("Fetching input for day {}", day)
+        })
+    }
+
+    /// As `cached_input`, calling `on_fetch` rather than printing when the input is fetched
+    pub fn cached_input_with(
+        &self,
+        input_root: &Path,
+        day: u8,
+        on_fetch: impl FnOnce(),
+    ) -> Result<String, FetchError> {
+        let path = input_root.join(format!("input_{}.txt", day));
+        let io_err = |err| FetchError::Io {
+            path: path.clone(),
+            err,
+        };
+
+        if path.exists() {
+            return std::fs::read_to_string(&path).map_err(io_err);
+        }
+
+        on_fetch();
+        let input = self.input(day)?;
+
+        std::fs::create_dir_all(input_root).map_err(io_err)?;
+        std::fs::write(&path, &input).map_err(io_err)?;
+
+        Ok(input)
+    }
+}
+
+/// A stand-in for adventofcode.com for testing against, serving canned responses
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// A request received by the stand-in server
+    #[derive(Debug)]
+    pub struct Request {
+        /// e.g. `GET /2024/day/5/input HTTP/1.1`
+        pub request_line: String,
+        pub headers: Vec<(String, String)>,
+        pub body: String,
+    }
+
+    impl Request {
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// Serve `responses` in order, one per connection, then stop
+    ///
+    /// Returns the server's base URL and a channel receiving each request as it's served.
+    pub fn serve(responses: Vec<(u16, &'static str)>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (key, value) = line.split_once(':').unwrap();
+                    headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+
+                let mut request = Request {
+                    request_line: request_line.trim_end().to_string(),
+                    headers,
+                    body: String::new(),
+                };
+
+                let content_length = request
+                    .header("content-length")
+                    .map_or(0, |len| len.parse().unwrap());
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                request.body = String::from_utf8(request_body).unwrap();
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stand-in\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+
+                let _ = tx.send(request);
+            }
+        });
+
+        (base_url, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn fetcher(base_url: String) -> Fetcher {
+        Fetcher::new(FetchConfig {
+            base_url,
+            session: Some("abc123".to_string()),
+            min_interval: Duration::ZERO,
+            retry_delay: Duration::from_millis(1),
+            ..FetchConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fetch_and_cache() {
+        let (base_url, requests) = stand_in::serve(vec![(200, "1 2\n3 4\n")]);
+        let fetcher = fetcher(base_url);
+        let tmp = TempDir::new("fetch_cache");
+        let root = tmp.path();
+
+        assert_eq!(fetcher.cached_input(root, 5).unwrap(), "1 2\n3 4\n");
+        let request = requests.recv().unwrap();
+        assert_eq!(request.request_line, "GET /2024/day/5/input HTTP/1.1");
+        assert_eq!(request.header("cookie"), Some("session=abc123"));
+
+        // Served from the cache, the stand-in would refuse a second connection
+        assert_eq!(fetcher.cached_input(root, 5).unwrap(), "1 2\n3 4\n");
+    }
+
+    #[test]
+    fn test_invalid_responses_not_cached() {
+        let (base_url, _requests) = stand_in::serve(vec![
+            (
+                404,
+                "Please don't repeatedly request this endpoint before it unlocks!",
+            ),
+            (200, "<!DOCTYPE html>\n<html></html>"),
+            (
+                400,
+                "Puzzle inputs differ by user.  Please log in to get your puzzle input.",
+            ),
+        ]);
+        let fetcher = fetcher(base_url);
+        let tmp = TempDir::new("fetch_invalid");
+        let root = tmp.path();
+
+        let err = fetcher.cached_input(root, 25).unwrap_err();
+        assert!(matches!(err, FetchError::NotUnlocked { .. }), "{err:?}");
+        let err = fetcher.cached_input(root, 25).unwrap_err();
+        assert!(matches!(err, FetchError::UnexpectedBody { .. }), "{err:?}");
+        let err = fetcher.cached_input(root, 25).unwrap_err();
+        assert!(matches!(err, FetchError::Unauthorized { .. }), "{err:?}");
+
+        assert!(!root.join("input_25.txt").exists());
+    }
+
+    #[test]
+    fn test_retry() {
+        let (base_url, _requests) = stand_in::serve(vec![(502, ""), (503, ""), (200, "42\n")]);
+        assert_eq!(fetcher(base_url).input(1).unwrap(), "42\n");
+
+        let (base_url, _requests) = stand_in::serve(vec![(500, ""), (500, ""), (500, "")]);
+        let err = fetcher(base_url).input(1).unwrap_err();
+        assert!(
+            matches!(err, FetchError::Status { status: 500, .. }),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_puzzle_page() {
+        let (base_url, requests) = stand_in::serve(vec![
+            (200, "<main><article class=\"day-desc\"></article></main>"),
+            (200, "<main>Sign in</main>"),
+        ]);
+        let fetcher = fetcher(base_url);
+
+        assert!(fetcher.puzzle_page(3).is_ok());
+        assert_eq!(
+            requests.recv().unwrap().request_line,
+            "GET /2024/day/3 HTTP/1.1"
+        );
+
+        let err = fetcher.puzzle_page(3).unwrap_err();
+        assert!(matches!(err, FetchError::UnexpectedBody { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let (base_url, _requests) = stand_in::serve(vec![(200, "1\n"), (200, "2\n")]);
+        let fetcher = Fetcher::new(FetchConfig {
+            min_interval: Duration::from_millis(100),
+            ..fetcher(base_url).config().clone()
+        })
+        .unwrap();
+
+        let sw = Instant::now();
+        fetcher.input(1).unwrap();
+        fetcher.input(2).unwrap();
+        assert!(sw.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_missing_session() {
+        let fetcher = Fetcher::new(FetchConfig::default()).unwrap();
+        let err = fetcher.input(1).unwrap_err();
+        assert!(matches!(err, FetchError::MissingSession), "{err:?}");
+    }
+}
@@ -0,0 +1,636 @@
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::bench::{BenchResult, Stats};
+use crate::error::Phase;
+use crate::inputs::MAIN_INPUT;
+use crate::Outcome;
+
+/// Summary of the timings of a single phase, enough to compare against another summary
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Timing {
+    pub samples: usize,
+    pub mean_ns: f64,
+
+    /// Population standard deviation of the samples
+    pub stddev_ns: f64,
+}
+
+impl Timing {
+    pub fn from_stats(stats: &Stats) -> Self {
+        Self {
+            samples: stats.samples,
+            mean_ns: stats.mean.as_nanos() as f64,
+            stddev_ns: stats.stddev.as_nanos() as f64,
+        }
+    }
+
+    /// Summary of the union of the samples of both timings
+    pub fn combine(&self, other: &Timing) -> Timing {
+        let (n1, n2) = (self.samples as f64, other.samples as f64);
+        let n = n1 + n2;
+        let mean = (n1 * self.mean_ns + n2 * other.mean_ns) / n;
+        let sum_squares = n1 * self.stddev_ns.powi(2)
+            + n2 * other.stddev_ns.powi(2)
+            + n1 * n2 / n * (self.mean_ns - other.mean_ns).powi(2);
+
+        Timing {
+            samples: self.samples + other.samples,
+            mean_ns: mean,
+            stddev_ns: (sum_squares / n).sqrt(),
+        }
+    }
+
+    /// Unbiased estimate of the variance of the population the samples came from
+    fn sample_variance(&self) -> Option<f64> {
+        let n = self.samples as f64;
+        (self.samples >= 2).then(|| self.stddev_ns.powi(2) * n / (n - 1.0))
+    }
+
+    pub fn mean(&self) -> Duration {
+        Duration::from_nanos(self.mean_ns.round() as u64)
+    }
+}
+
+/// The timings of one day on one input from a single bench, stored as a line of the history file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Commit the run was built from, suffixed with `-dirty` if there were uncommitted changes
+    pub commit: Option<String>,
+
+    /// Seconds since the unix epoch
+    pub timestamp: u64,
+
+    pub day: u8,
+    pub name: String,
+    pub input: String,
+
+    /// Timings of each phase that completed successfully
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse: Option<Timing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_1: Option<Timing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_2: Option<Timing>,
+}
+
+impl HistoryEntry {
+    pub fn from_bench(result: &BenchResult, commit: Option<String>, timestamp: u64) -> Self {
+        let answered = |outcome: &Outcome| outcome.answer().is_some();
+        let parsed = [&result.p1_result, &result.p2_result]
+            .into_iter()
+            .all(|outcome| !matches!(outcome, Outcome::Error(err) if err.phase == Phase::Parse));
+
+        Self {
+            commit,
+            timestamp,
+            day: result.name.day,
+            name: result.name.name.to_string(),
+            input: MAIN_INPUT.to_string(),
+            parse: parsed.then(|| Timing::from_stats(&result.parse)),
+            part_1: result
+                .p1
+                .as_ref()
+                .filter(|_| answered(&result.p1_result))
+                .map(Timing::from_stats),
+            part_2: result
+                .p2
+                .as_ref()
+                .filter(|_| answered(&result.p2_result))
+                .map(Timing::from_stats),
+        }
+    }
+
+    fn phases(&self) -> [(Phase, Option<Timing>); 3] {
+        [
+            (Phase::Parse, self.parse),
+            (Phase::Part1, self.part_1),
+            (Phase::Part2, self.part_2),
+        ]
+    }
+}
+
+/// The commit of the working tree the process was started in, if it's in a git repository
+pub fn current_commit() -> Option<String> {
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let commit = git(&["rev-parse", "--short=12", "HEAD"])?;
+    let dirty = !git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty();
+
+    Some(if dirty {
+        format!("{commit}-dirty")
+    } else {
+        commit
+    })
+}
+
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs())
+}
+
+/// Append entries to the JSON lines history file at `path`, creating it if needed
+pub fn append(path: &Path, entries: &[HistoryEntry]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut lines = String::new();
+    for entry in entries {
+        lines += &serde_json::to_string(entry).expect("History entries are always serializable");
+        lines.push('\n');
+    }
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| format!("Failed to append to {}", path.display()))
+}
+
+/// Load every entry in the history file at `path`, treating a missing file as an empty history
+pub fn load(path: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid entry on line {} of {}", idx + 1, path.display()))
+        })
+        .collect()
+}
+
+/// The entries for the baseline commit, given as a commit prefix, or `last` for the most
+/// recently recorded commit other than `current_commit`
+///
+/// Returns the full baseline commit along with its entries.
+pub fn baseline<'a>(
+    history: &'a [HistoryEntry],
+    baseline: &str,
+    current_commit: Option<&str>,
+) -> anyhow::Result<(String, Vec<&'a HistoryEntry>)> {
+    let commit = if baseline == "last" {
+        history
+            .iter()
+            .rev()
+            .filter_map(|entry| entry.commit.as_deref())
+            .find(|&commit| Some(commit) != current_commit)
+            .context("No runs from another commit in the history")?
+    } else {
+        let mut matches = history
+            .iter()
+            .filter_map(|entry| entry.commit.as_deref())
+            .filter(|commit| commit.starts_with(baseline))
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches.dedup();
+
+        match matches[..] {
+            [commit] => commit,
+            [] => anyhow::bail!("No runs from commit {baseline:?} in the history"),
+            _ => anyhow::bail!("Commit {baseline:?} is ambiguous: {}", matches.join(", ")),
+        }
+    };
+
+    let entries = history
+        .iter()
+        .filter(|entry| entry.commit.as_deref() == Some(commit))
+        .collect();
+    Ok((commit.to_string(), entries))
+}
+
+/// Natural log of the gamma function, by the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series = COEFFS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |acc, (i, c)| {
+            acc + c / (x + 1.0 + i as f64)
+        });
+
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Continued fraction for the regularized incomplete beta function, by the modified Lentz method
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+
+    for m in 1..300 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+
+    h
+}
+
+/// The regularized incomplete beta function I_x(a, b)
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WelchTest {
+    pub t: f64,
+    pub df: f64,
+
+    /// Two-sided p-value for the means being equal
+    pub p: f64,
+}
+
+/// Welch's t-test for the means of two timings being equal, if both have at least two samples
+pub fn welch_t_test(a: &Timing, b: &Timing) -> Option<WelchTest> {
+    let var_a = a.sample_variance()? / a.samples as f64;
+    let var_b = b.sample_variance()? / b.samples as f64;
+    let diff = a.mean_ns - b.mean_ns;
+
+    if var_a + var_b == 0.0 {
+        let p = if diff == 0.0 { 1.0 } else { 0.0 };
+        return Some(WelchTest {
+            t: diff.signum() * f64::INFINITY,
+            df: (a.samples + b.samples - 2) as f64,
+            p,
+        });
+    }
+
+    let t = diff / (var_a + var_b).sqrt();
+    let df = (var_a + var_b).powi(2)
+        / (var_a.powi(2) / (a.samples - 1) as f64 + var_b.powi(2) / (b.samples - 1) as f64);
+    let p = incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+
+    Some(WelchTest { t, df, p })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Significantly slower than the baseline
+    Regression,
+
+    /// Significantly faster than the baseline
+    Improvement,
+
+    Unchanged,
+
+    /// Too few samples on one side to tell
+    Inconclusive,
+}
+
+impl Verdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Regression => "REGRESSION",
+            Verdict::Improvement => "improvement",
+            Verdict::Unchanged => "",
+            Verdict::Inconclusive => "?",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub day: u8,
+    pub name: String,
+    pub input: String,
+    pub phase: Phase,
+    pub baseline: Timing,
+    pub current: Timing,
+    pub test: Option<WelchTest>,
+    pub verdict: Verdict,
+}
+
+impl Comparison {
+    /// Change in mean time relative to the baseline, e.g. 0.1 for 10% slower
+    pub fn change(&self) -> f64 {
+        self.current.mean_ns / self.baseline.mean_ns - 1.0
+    }
+}
+
+/// Compare each phase of each current entry against all of the baseline's entries for the same
+/// day and input
+///
+/// A phase is only flagged as a regression or improvement if the difference is significant at
+/// level `alpha`, and the mean changed by more than the fraction `min_change`.
+pub fn compare(
+    baseline: &[&HistoryEntry],
+    current: &[HistoryEntry],
+    alpha: f64,
+    min_change: f64,
+) -> Vec<Comparison> {
+    let mut comparisons = Vec::new();
+
+    for entry in current {
+        let base_entries = baseline
+            .iter()
+            .filter(|base| base.day == entry.day && base.input == entry.input)
+            .collect::<Vec<_>>();
+
+        for (idx, (phase, current)) in entry.phases().into_iter().enumerate() {
+            let Some(current) = current else {
+                continue;
+            };
+            let Some(base) = base_entries
+                .iter()
+                .filter_map(|base| base.phases()[idx].1)
+                .reduce(|a, b| a.combine(&b))
+            else {
+                continue;
+            };
+
+            let test = welch_t_test(&current, &base);
+            let mut comparison = Comparison {
+                day: entry.day,
+                name: entry.name.clone(),
+                input: entry.input.clone(),
+                phase,
+                baseline: base,
+                current,
+                test,
+                verdict: Verdict::Inconclusive,
+            };
+
+            comparison.verdict = match test {
+                None => Verdict::Inconclusive,
+                Some(test) if test.p < alpha && comparison.change().abs() > min_change => {
+                    if comparison.change() > 0.0 {
+                        Verdict::Regression
+                    } else {
+                        Verdict::Improvement
+                    }
+                }
+                Some(_) => Verdict::Unchanged,
+            };
+            comparisons.push(comparison);
+        }
+    }
+
+    comparisons
+}
+
+pub fn print_comparison_table(comparisons: &[Comparison]) {
+    if comparisons.is_empty() {
+        return;
+    }
+
+    const HEADERS: [&str; 8] = [
+        "Day", "Name", "Phase", "Baseline", "Current", "Change", "p", "Verdict",
+    ];
+
+    let rows = comparisons
+        .iter()
+        .map(|c| {
+            let name = if c.input == MAIN_INPUT {
+                c.name.clone()
+            } else {
+                format!("{} [{}]", c.name, c.input)
+            };
+
+            [
+                format!("{:02}", c.day),
+                name,
+                c.phase.to_string(),
+                format!("{:?}", c.baseline.mean()),
+                format!("{:?}", c.current.mean()),
+                format!("{:+.1}%", c.change() * 100.0),
+                c.test
+                    .map_or("-".to_string(), |test| format!("{:.3}", test.p)),
+                c.verdict.as_str().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = std::cmp::max(*width, cell.len());
+        }
+    }
+
+    let format_row = |cells: &[&str]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let header = format_row(&HEADERS);
+    println!("{}", header);
+    println!("{}", "-".repeat(header.len()));
+
+    for row in &rows {
+        println!("{}", format_row(&row.each_ref().map(String::as_str)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::{bench_day, BenchOptions};
+    use crate::select::Parts;
+    use crate::test_util::{day, TempDir};
+
+    fn timing(samples: &[f64]) -> Timing {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        Timing {
+            samples: samples.len(),
+            mean_ns: mean,
+            stddev_ns: variance.sqrt(),
+        }
+    }
+
+    fn entry(commit: &str, timestamp: u64, part_1: Timing) -> HistoryEntry {
+        HistoryEntry {
+            commit: Some(commit.to_string()),
+            timestamp,
+            day: 1,
+            name: "Historian Hysteria".to_string(),
+            input: MAIN_INPUT.to_string(),
+            parse: None,
+            part_1: Some(part_1),
+            part_2: None,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_combine() {
+        let combined = timing(&[1.0, 2.0, 3.0]).combine(&timing(&[10.0, 20.0]));
+        let expected = timing(&[1.0, 2.0, 3.0, 10.0, 20.0]);
+        assert_eq!(combined.samples, expected.samples);
+        assert_close(combined.mean_ns, expected.mean_ns);
+        assert_close(combined.stddev_ns, expected.stddev_ns);
+    }
+
+    #[test]
+    fn test_incomplete_beta() {
+        assert_close(incomplete_beta(2.0, 3.0, 0.4), 0.5248);
+        assert_close(incomplete_beta(0.5, 0.5, 0.5), 0.5);
+        assert_eq!(incomplete_beta(1.0, 1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_welch() {
+        // Example 1 from https://en.wikipedia.org/wiki/Welch%27s_t-test
+        let a = timing(&[
+            27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7,
+            21.4,
+        ]);
+        let b = timing(&[
+            27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5,
+            24.4,
+        ]);
+
+        let test = welch_t_test(&a, &b).unwrap();
+        assert!((test.t - -2.46).abs() < 0.01, "{}", test.t);
+        assert!((test.df - 24.99).abs() < 0.01, "{}", test.df);
+        assert!((test.p - 0.021).abs() < 0.001, "{}", test.p);
+
+        assert!(welch_t_test(&timing(&[5000.0]), &b).is_none());
+    }
+
+    #[test]
+    fn test_compare() {
+        let history = vec![
+            entry("aaaa", 1, timing(&[100.0, 101.0, 99.0, 100.0])),
+            entry("bbbb", 2, timing(&[100.0, 102.0, 98.0])),
+            entry("bbbb", 3, timing(&[101.0, 99.0, 100.0])),
+        ];
+
+        let (commit, base) = baseline(&history, "last", Some("cccc")).unwrap();
+        assert_eq!(commit, "bbbb");
+        assert_eq!(base.len(), 2);
+        assert_eq!(baseline(&history, "last", Some("bbbb")).unwrap().0, "aaaa");
+        assert_eq!(baseline(&history, "aa", None).unwrap().0, "aaaa");
+        assert!(baseline(&history, "dd", None).is_err());
+
+        let current = [
+            entry("cccc", 4, timing(&[150.0, 151.0, 149.0, 150.0])),
+            HistoryEntry {
+                day: 2,
+                ..entry("cccc", 4, timing(&[1.0, 2.0]))
+            },
+        ];
+        let comparisons = compare(&base, &current, 0.05, 0.05);
+        assert_eq!(comparisons.len(), 1);
+        assert_eq!(comparisons[0].verdict, Verdict::Regression);
+        assert_close(comparisons[0].change(), 0.5);
+
+        let current = [entry("cccc", 4, timing(&[100.5, 101.5, 99.5, 100.5]))];
+        let comparisons = compare(&base, &current, 0.05, 0.05);
+        assert_eq!(comparisons[0].verdict, Verdict::Unchanged);
+    }
+
+    #[test]
+    fn test_from_bench() {
+        let day = day(2);
+        let opts = BenchOptions {
+            warmup: 0,
+            samples: 3,
+            time_budget: None,
+            parts: Parts::only(2),
+        };
+
+        let result = bench_day(day.as_ref(), example!(2, 1), &opts);
+        let entry = HistoryEntry::from_bench(&result, None, 0);
+        assert_eq!(entry.parse.map(|t| t.samples), Some(3));
+        assert!(entry.part_1.is_none());
+        assert_eq!(entry.part_2.map(|t| t.samples), Some(3));
+
+        // Neither a failed parse nor the parts it stopped are recorded
+        let result = bench_day(day.as_ref(), "not a report", &opts);
+        let entry = HistoryEntry::from_bench(&result, None, 0);
+        assert_eq!(
+            (entry.parse, entry.part_1, entry.part_2),
+            (None, None, None)
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let tmp = TempDir::new("history");
+        let path = tmp.path().join("history.jsonl");
+
+        let entries = [entry("aaaa", 1, timing(&[1.0, 2.0]))];
+        append(&path, &entries).unwrap();
+        append(&path, &entries).unwrap();
+        let loaded = load(&path).unwrap();
+
+        assert_eq!(loaded, [entries[0].clone(), entries[0].clone()]);
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::answers::{AnswerStore, PartAnswers};
+use crate::fetch::Fetcher;
+use crate::RunResult;
+
+/// Label of the main input for each day, `input_<day>.txt` in the input root
+pub const MAIN_INPUT: &str = "main";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// The day's main puzzle input, fetched and cached if it isn't already present
+    Main,
+
+    /// An additional input file, e.g. a puzzle example or another account's input
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct InputCase {
+    pub label: String,
+    pub source: InputSource,
+
+    /// Known-correct answers for this input, if any have been recorded
+    pub expected: PartAnswers,
+}
+
+impl InputCase {
+    pub fn load(&self, fetcher: &Fetcher, input_root: &Path, day: u8) -> anyhow::Result<String> {
+        match &self.source {
+            InputSource::Main => Ok(fetcher.cached_input(input_root, day)?),
+            InputSource::File(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Where the expected answers for this input are stored, for inputs that store them
+    /// alongside the input file rather than in the main answer store
+    pub fn answers_path(&self) -> Option<PathBuf> {
+        match &self.source {
+            InputSource::Main => None,
+            InputSource::File(path) => Some(path.with_extension("answers.toml")),
+        }
+    }
+
+    /// Compare the given run against this input's expected answers
+    pub fn check(&self, result: &mut RunResult) {
+        self.expected.check(result);
+    }
+}
+
+/// Directory holding a day's additional inputs, e.g. `inputs/day_5/`
+pub fn input_set_dir(input_root: &Path, day: u8) -> PathBuf {
+    input_root.join(format!("day_{}", day))
+}
+
+/// Every input a day should be run against: its main input, followed by each `*.txt` file in
+/// its input set directory in name order
+///
+/// An additional input's expected answers are read from a sibling file with the extension
+/// `.answers.toml`, e.g. `inputs/day_5/example.answers.toml` for `inputs/day_5/example.txt`.
+pub fn input_set(
+    input_root: &Path,
+    day: u8,
+    answers: &AnswerStore,
+) -> anyhow::Result<Vec<InputCase>> {
+    let mut cases = vec![InputCase {
+        label: MAIN_INPUT.to_string(),
+        source: InputSource::Main,
+        expected: answers.get(day).cloned().unwrap_or_default(),
+    }];
+
+    let dir = input_set_dir(input_root, day);
+    if !dir.is_dir() {
+        return Ok(cases);
+    }
+
+    let mut paths = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"));
+    paths.sort();
+
+    for path in paths {
+        let label = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .with_context(|| format!("Invalid input file name {}", path.display()))?
+            .to_string();
+
+        let answers_path = path.with_extension("answers.toml");
+        let expected = PartAnswers::load(&answers_path)?.unwrap_or_default();
+
+        cases.push(InputCase {
+            label,
+            source: InputSource::File(path),
+            expected,
+        });
+    }
+
+    Ok(cases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_input_set() {
+        let tmp = TempDir::new("input_set");
+        let root = tmp.path();
+        let dir = input_set_dir(root, 5);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("example.txt"), "1|2\n\n1,2\n").unwrap();
+        std::fs::write(dir.join("example.answers.toml"), "part_1 = \"143\"\n").unwrap();
+        std::fs::write(dir.join("alice.txt"), "").unwrap();
+        std::fs::write(dir.join("notes.md"), "").unwrap();
+
+        let answers = AnswerStore::parse("[day_05]\npart_2 = \"4077\"\n").unwrap();
+        let cases = input_set(root, 5, &answers).unwrap();
+
+        let labels = cases.iter().map(|c| c.label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, [MAIN_INPUT, "alice", "example"]);
+
+        assert_eq!(cases[0].source, InputSource::Main);
+        assert_eq!(cases[0].expected.part_2.as_deref(), Some("4077"));
+        assert_eq!(cases[1].expected, PartAnswers::default());
+        assert_eq!(cases[2].expected.part_1.as_deref(), Some("143"));
+        assert_eq!(
+            cases[2].answers_path(),
+            Some(dir.join("example.answers.toml"))
+        );
+    }
+
+    #[test]
+    fn test_missing_input_set() {
+        let tmp = TempDir::new("input_set_missing");
+        let cases = input_set(tmp.path(), 5, &AnswerStore::default()).unwrap();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].source, InputSource::Main);
+    }
+}
@@ -1,206 +1,394 @@
-use std::time::{Duration, Instant};
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloc::AllocStats;
+use answers::AnswerStatus;
+use error::{DayError, DayErrorKind, InputError, IntoAnswer, Phase};
+use perf::PerfCounts;
+use runner::{run_phase, run_phase_isolated, Measurement, PhaseHandle, PhaseOutput, RunOptions};
+use select::Parts;
+
+/// A puzzle example saved by `aoc fetch-puzzle`, e.g. `example!(5, 1)` for the first example in
+/// the day 5 puzzle description, saved as `puzzles/day_5/example_1.txt`
+#[cfg(test)]
+macro_rules! example {
+    ($day:literal, $index:literal) => {
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/puzzles/day_",
+            $day,
+            "/example_",
+            $index,
+            ".txt"
+        ))
+    };
+}
 
+pub mod alloc;
+pub mod answers;
+pub mod bench;
+pub mod error;
+pub mod fetch;
+pub mod history;
+pub mod inputs;
+pub mod perf;
+pub mod puzzle;
+pub mod report;
+pub mod runner;
+pub mod scaffold;
+pub mod select;
+pub mod submit;
+#[cfg(test)]
+mod test_util;
+pub mod tui;
 pub mod util;
+pub mod watch;
+
+pub use report::print_results_table;
 
-#[derive(Debug, Clone, Copy)]
+#[cfg(feature = "alloc-profile")]
+#[global_allocator]
+static ALLOCATOR: alloc::CountingAllocator = alloc::CountingAllocator;
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
 pub struct DayName {
     pub name: &'static str,
     pub day: u8,
 }
 
-#[derive(Debug)]
+/// The outcome of running a single part of a day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Answer(String),
+    Error(DayError),
+
+    /// The part wasn't selected to run
+    Skipped,
+}
+
+impl Outcome {
+    pub fn answer(&self) -> Option<&str> {
+        match self {
+            Outcome::Answer(answer) => Some(answer),
+            Outcome::Error(_) | Outcome::Skipped => None,
+        }
+    }
+
+    fn from_phase(day: u8, phase: Phase, output: PhaseOutput<Result<String, InputError>>) -> Self {
+        let kind = match output {
+            PhaseOutput::Done(Ok(answer)) => return Outcome::Answer(answer),
+            PhaseOutput::Done(Err(err)) => DayErrorKind::Input(err),
+            PhaseOutput::Panic(message) => DayErrorKind::Panic(message),
+            PhaseOutput::Timeout(timeout) => DayErrorKind::Timeout(timeout),
+        };
+
+        Outcome::Error(DayError { day, phase, kind })
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Answer(answer) => f.write_str(answer),
+            Outcome::Error(err) => f.write_str(err.label()),
+            Outcome::Skipped => f.write_str("-"),
+        }
+    }
+}
+
+impl serde::Serialize for Outcome {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Outcome::Skipped => serializer.serialize_none(),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
 pub struct RunResult {
+    #[serde(flatten)]
     pub name: DayName,
+
+    /// Label of the input this run used, see `inputs::input_set`
+    pub input: String,
+    #[serde(rename = "parse_time_ns", serialize_with = "report::serialize_nanos")]
     pub parse_time: Duration,
+    #[serde(rename = "p1_time_ns", serialize_with = "report::serialize_nanos")]
     pub p1_time: Duration,
+    #[serde(rename = "p2_time_ns", serialize_with = "report::serialize_nanos")]
     pub p2_time: Duration,
-    pub p1_result: String,
-    pub p2_result: String,
+    pub p1_result: Outcome,
+    pub p2_result: Outcome,
+
+    /// Heap usage of each phase, only measured when built with the `alloc-profile` feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_alloc: Option<AllocStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p1_alloc: Option<AllocStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p2_alloc: Option<AllocStats>,
+
+    /// Hardware counters for each phase, only recorded when built with the `perf-counters`
+    /// feature on Linux
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_counters: Option<PerfCounts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p1_counters: Option<PerfCounts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p2_counters: Option<PerfCounts>,
+
+    /// Whether each part's result matches the stored answer, if it has been checked
+    #[serde(rename = "p1_check", skip_serializing_if = "Option::is_none")]
+    pub p1_status: Option<AnswerStatus>,
+    #[serde(rename = "p2_check", skip_serializing_if = "Option::is_none")]
+    pub p2_status: Option<AnswerStatus>,
 }
 
 impl RunResult {
     pub fn total_time(&self) -> Duration {
         self.parse_time + self.p1_time + self.p2_time
     }
+
+    pub fn has_alloc_stats(&self) -> bool {
+        self.parse_alloc.is_some() || self.p1_alloc.is_some() || self.p2_alloc.is_some()
+    }
+
+    pub fn has_counters(&self) -> bool {
+        self.parse_counters.is_some() || self.p1_counters.is_some() || self.p2_counters.is_some()
+    }
+
+    /// All errors produced by this run, reporting a parse error only once
+    pub fn errors(&self) -> impl Iterator<Item = &DayError> {
+        let p1_error = match &self.p1_result {
+            Outcome::Error(err) => Some(err),
+            _ => None,
+        };
+        let p2_error = match &self.p2_result {
+            Outcome::Error(err) if p1_error != Some(err) => Some(err),
+            _ => None,
+        };
+        p1_error.into_iter().chain(p2_error)
+    }
+}
+
+/// A day's parsed input, opaque outside of the day that parsed it
+///
+/// Cheap to clone, so a single parse can be shared by any number of part runs.
+#[derive(Clone)]
+pub struct ParsedHandle {
+    day: DayName,
+    input: Arc<dyn Any + Send + Sync>,
+}
+
+impl ParsedHandle {
+    /// The day that parsed this input
+    pub fn day(&self) -> DayName {
+        self.day
+    }
+
+    fn downcast<T: 'static>(&self, day: DayName) -> &T {
+        match self.input.downcast_ref() {
+            Some(input) if self.day.day == day.day => input,
+            _ => panic!(
+                "Input parsed by day {} passed to day {}",
+                self.day.day, day.day
+            ),
+        }
+    }
 }
 
+impl std::fmt::Debug for ParsedHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParsedHandle")
+            .field("day", &self.day)
+            .finish_non_exhaustive()
+    }
+}
+
+type ParseFn<ParsedInput> = Arc<dyn Fn(&str) -> Result<ParsedInput, InputError> + Send + Sync>;
+type PartFn<Input, Result> = Arc<dyn Fn(&Input) -> Result + Send + Sync>;
+type PhaseResult<T> = (Measurement, PhaseOutput<Result<T, InputError>>);
+
 pub struct Day<ParsedInput, P1Input, P1Result, P2Input, P2Result>
 where
     ParsedInput: AsRef<P1Input> + AsRef<P2Input>,
     P1Input: ?Sized,
-    P1Result: std::fmt::Display,
+    P1Result: IntoAnswer,
     P2Input: ?Sized,
-    P2Result: std::fmt::Display,
+    P2Result: IntoAnswer,
 {
     name: DayName,
-    parse: Box<dyn Fn(&str) -> ParsedInput>,
-    part_1: Box<dyn Fn(&P1Input) -> P1Result>,
-    part_2: Box<dyn Fn(&P2Input) -> P2Result>,
+    parse: ParseFn<ParsedInput>,
+    part_1: PartFn<P1Input, P1Result>,
+    part_2: PartFn<P2Input, P2Result>,
 }
 
-pub trait ErasedDay {
+pub trait ErasedDay: Send + Sync {
     fn name(&self) -> DayName;
-    fn run(&self, input: &str) -> RunResult;
+
+    /// Parse the input, for any number of later calls to `part_1` and `part_2`
+    fn parse(&self, input: &str) -> Result<ParsedHandle, InputError>;
+
+    /// Run part 1 over an input parsed by this day, panicking if it was parsed by another day
+    fn part_1(&self, parsed: &ParsedHandle) -> Result<String, InputError>;
+
+    /// Run part 2 over an input parsed by this day, panicking if it was parsed by another day
+    fn part_2(&self, parsed: &ParsedHandle) -> Result<String, InputError>;
+
+    /// Run the parse and the selected parts on the current thread, catching any panics
+    fn run(&self, input: &str, parts: Parts) -> RunResult;
+
+    /// Run the parse and each selected part on its own worker thread, abandoning any that exceeds
+    /// the timeout
+    fn run_isolated(&self, input: &str, opts: &RunOptions) -> RunResult;
 }
 
-impl<ParsedInput, P1Input, P1Result, P2Input, P2Result> ErasedDay
-    for Day<ParsedInput, P1Input, P1Result, P2Input, P2Result>
+impl<ParsedInput, P1Input, P1Result, P2Input, P2Result>
+    Day<ParsedInput, P1Input, P1Result, P2Input, P2Result>
 where
     ParsedInput: AsRef<P1Input> + AsRef<P2Input>,
     P1Input: ?Sized,
-    P1Result: std::fmt::Display,
+    P1Result: IntoAnswer,
     P2Input: ?Sized,
-    P2Result: std::fmt::Display,
+    P2Result: IntoAnswer,
 {
-    fn name(&self) -> DayName {
-        self.name
-    }
-
-    fn run(&self, input: &str) -> RunResult {
-        let sw = Instant::now();
-        let parsed_input = (self.parse)(input);
-        let parse_time = sw.elapsed();
-
-        let sw = Instant::now();
-        let p1_result = (self.part_1)(parsed_input.as_ref());
-        let p1_time = sw.elapsed();
-        let p1_result = format!("{}", p1_result);
-
-        let sw = Instant::now();
-        let p2_result = (self.part_2)(parsed_input.as_ref());
-        let p2_time = sw.elapsed();
-        let p2_result = format!("{}", p2_result);
+    /// `run_parts` returns `None` for each part that wasn't selected to run
+    fn result(
+        &self,
+        parts: Parts,
+        parse: PhaseResult<ParsedInput>,
+        run_parts: impl FnOnce(
+            ParsedInput,
+        ) -> (Option<PhaseResult<String>>, Option<PhaseResult<String>>),
+    ) -> RunResult {
+        let day = self.name.day;
+        let (parse_measurement, parsed_input) = parse;
+
+        let part_outcome = |phase, output: Option<PhaseResult<String>>| match output {
+            Some((measurement, output)) => (measurement, Outcome::from_phase(day, phase, output)),
+            None => (Measurement::default(), Outcome::Skipped),
+        };
+
+        let ((p1_measurement, p1_result), (p2_measurement, p2_result)) = match parsed_input {
+            PhaseOutput::Done(Ok(parsed_input)) => {
+                let (p1_output, p2_output) = run_parts(parsed_input);
+                (
+                    part_outcome(Phase::Part1, p1_output),
+                    part_outcome(Phase::Part2, p2_output),
+                )
+            }
+            failed => {
+                // Neither part can run without a parsed input, so both share the parse failure
+                let failed = failed.map(|result| result.map(|_| unreachable!()));
+                let outcome = Outcome::from_phase(day, Phase::Parse, failed);
+                let shared = |selected| match selected {
+                    true => (Measurement::default(), outcome.clone()),
+                    false => (Measurement::default(), Outcome::Skipped),
+                };
+                (shared(parts.part_1), shared(parts.part_2))
+            }
+        };
 
         RunResult {
             name: self.name,
-            parse_time,
-            p1_time,
-            p2_time,
+            input: inputs::MAIN_INPUT.to_string(),
+            parse_time: parse_measurement.time,
+            p1_time: p1_measurement.time,
+            p2_time: p2_measurement.time,
             p1_result,
             p2_result,
+            parse_alloc: parse_measurement.alloc,
+            p1_alloc: p1_measurement.alloc,
+            p2_alloc: p2_measurement.alloc,
+            parse_counters: parse_measurement.counters,
+            p1_counters: p1_measurement.counters,
+            p2_counters: p2_measurement.counters,
+            p1_status: None,
+            p2_status: None,
         }
     }
 }
 
-pub fn get_input(input_root: &std::path::Path, day_name: DayName) -> anyhow::Result<String> {
-    let file_name = format!("input_{}.txt", day_name.day);
-    let mut path = input_root.to_path_buf();
-    path.push(file_name);
-
-    let input = if path.exists() {
-        std::fs::read_to_string(path)?
-    } else {
-        println!("Fetching input for day {}", day_name.day);
-
-        let url = format!("https://adventofcode.com/2024/day/{}/input", day_name.day);
-        let session_cookie = std::env::var("AOC_SESSION_COOKIE")
-            .expect("Input not cached, and AOC_SESSION_COOKIE not set");
-
-        let jar = reqwest::cookie::Jar::default();
-        jar.add_cookie_str(
-            &format!("session={session_cookie}"),
-            &"https://adventofcode.com".parse().unwrap(),
-        );
-        let client = reqwest::blocking::ClientBuilder::default()
-            .cookie_provider(std::sync::Arc::new(jar))
-            .user_agent("github/joey9801")
-            .build()?;
-
-        let input = client.get(url).send()?.text()?;
-
-        std::fs::create_dir_all(path.parent().unwrap())?;
-        std::fs::write(path, &input)?;
+impl<ParsedInput, P1Input, P1Result, P2Input, P2Result> ErasedDay
+    for Day<ParsedInput, P1Input, P1Result, P2Input, P2Result>
+where
+    ParsedInput: AsRef<P1Input> + AsRef<P2Input> + Send + Sync + 'static,
+    P1Input: ?Sized + 'static,
+    P1Result: IntoAnswer + 'static,
+    P2Input: ?Sized + 'static,
+    P2Result: IntoAnswer + 'static,
+{
+    fn name(&self) -> DayName {
+        self.name
+    }
 
-        input
-    };
+    fn parse(&self, input: &str) -> Result<ParsedHandle, InputError> {
+        Ok(ParsedHandle {
+            day: self.name,
+            input: Arc::new((self.parse)(input)?),
+        })
+    }
 
-    Ok(input)
-}
-
-pub fn print_results_table(results: &[RunResult]) {
-    if results.is_empty() {
-        return;
-    }
-
-    fn col_width(results: &[RunResult], title: &str, len: impl Fn(&RunResult) -> usize) -> usize {
-        std::cmp::max(title.len(), results.iter().map(len).max().unwrap())
-    }
-
-    let name_width = col_width(results, "Name", |r| r.name.name.len());
-    let p1_result_width = col_width(results, "P1 result", |r| r.p1_result.len());
-    let p2_result_width = col_width(results, "P2 result", |r| r.p2_result.len());
-    let parse_time_width = col_width(results, "Parse time", |r| {
-        format!("{:?}", r.parse_time).len()
-    });
-    let p1_time_width = col_width(results, "P1 time", |r| format!("{:?}", r.p1_time).len());
-    let p2_time_width = col_width(results, "P2 time", |r| format!("{:?}", r.p2_time).len());
-
-    let total_parse_time: Duration = results.iter().map(|r| r.parse_time).sum();
-    let total_p1_time: Duration = results.iter().map(|r| r.p1_time).sum();
-    let total_p2_time: Duration = results.iter().map(|r| r.p2_time).sum();
-    let total_time: Duration = results.iter().map(|r| r.total_time()).sum();
-
-    let parse_time_width = std::cmp::max(parse_time_width, format!("{:?}", total_parse_time).len());
-    let p1_time_width = std::cmp::max(p1_time_width, format!("{:?}", total_p1_time).len());
-    let p2_time_width = std::cmp::max(p2_time_width, format!("{:?}", total_p2_time).len());
-
-    let total_offset = 5 // "day |"
-        + name_width + 3
-        + p1_result_width + 3
-        + p2_result_width + 1;
-
-    let header = format!(
-        "Day | {:name_width$} | {:p1_result_width$} | {:p2_result_width$} | {:parse_time_width$} | {:p1_time_width$} | {:p2_time_width$} | Total time",
-        "Name",
-        "P1 result",
-        "P2 result",
-        "Parse time",
-        "P1 time",
-        "P2 time",
-        name_width = name_width,
-        p1_result_width = p1_result_width,
-        p2_result_width = p2_result_width,
-        parse_time_width = parse_time_width,
-        p1_time_width = p1_time_width,
-        p2_time_width = p2_time_width,
-    );
-
-    println!("{}", header);
-    for _ in 0..header.len() {
-        print!("-");
-    }
-    println!();
-
-    let print = |result: &RunResult| {
-        println!("{:02}  | {:name_width$} | {:p1_result_width$} | {:p2_result_width$} | {:parse_time_width$?} | {:p1_time_width$?} | {:p2_time_width$?} | {:?}",
-            result.name.day,
-            result.name.name,
-            result.p1_result,
-            result.p2_result,
-            result.parse_time,
-            result.p1_time,
-            result.p2_time,
-            result.total_time(),
-        )
-    };
+    fn part_1(&self, parsed: &ParsedHandle) -> Result<String, InputError> {
+        let parsed_input: &ParsedInput = parsed.downcast(self.name);
+        (self.part_1)(parsed_input.as_ref()).into_answer()
+    }
 
-    for result in results {
-        print(result);
+    fn part_2(&self, parsed: &ParsedHandle) -> Result<String, InputError> {
+        let parsed_input: &ParsedInput = parsed.downcast(self.name);
+        (self.part_2)(parsed_input.as_ref()).into_answer()
     }
 
-    for _ in 0..header.len() {
-        print!("-");
+    fn run(&self, input: &str, parts: Parts) -> RunResult {
+        let parse = run_phase(|| (self.parse)(input));
+
+        self.result(parts, parse, |parsed_input| {
+            let p1 = parts
+                .part_1
+                .then(|| run_phase(|| (self.part_1)(parsed_input.as_ref()).into_answer()));
+            let p2 = parts
+                .part_2
+                .then(|| run_phase(|| (self.part_2)(parsed_input.as_ref()).into_answer()));
+            (p1, p2)
+        })
     }
-    println!();
 
-    println!(
-        "{:>total_offset$} | {:parse_time_width$?} | {:p1_time_width$?} | {:p2_time_width$?} | {:?}",
-        "Total",
-        total_parse_time,
-        total_p1_time,
-        total_p2_time,
-        total_time,
-    );
+    fn run_isolated(&self, input: &str, opts: &RunOptions) -> RunResult {
+        let parse_fn = self.parse.clone();
+        let input = input.to_string();
+        let parse = run_phase_isolated(opts.timeout, move || parse_fn(&input));
+
+        let parts = opts.parts;
+        self.result(parts, parse, |parsed_input| {
+            let parsed_input = Arc::new(parsed_input);
+
+            let part_1 = self.part_1.clone();
+            let p1_input = parsed_input.clone();
+            let p1 = parts
+                .part_1
+                .then(|| PhaseHandle::spawn(move || part_1((*p1_input).as_ref()).into_answer()));
+
+            let part_2 = self.part_2.clone();
+            let spawn_p2 = || {
+                parts.part_2.then(|| {
+                    PhaseHandle::spawn(move || part_2((*parsed_input).as_ref()).into_answer())
+                })
+            };
+
+            if opts.parallel_parts {
+                let p2 = spawn_p2();
+                (
+                    p1.map(|p1| p1.wait(opts.timeout)),
+                    p2.map(|p2| p2.wait(opts.timeout)),
+                )
+            } else {
+                let p1 = p1.map(|p1| p1.wait(opts.timeout));
+                (p1, spawn_p2().map(|p2| p2.wait(opts.timeout)))
+            }
+        })
+    }
 }
 
 macro_rules! define_days {
@@ -213,9 +401,9 @@ macro_rules! define_days {
             vec![$(
                 Box::new(Day {
                     name: DayName { name: $name, day: $day_num },
-                    parse: Box::new($mod::parse),
-                    part_1: Box::new($mod::solve_part_1),
-                    part_2: Box::new($mod::solve_part_2),
+                    parse: Arc::new($mod::parse),
+                    part_1: Arc::new($mod::solve_part_1),
+                    part_2: Arc::new($mod::solve_part_2),
                 })
             ),*]
         }
@@ -238,3 +426,30 @@ define_days! {
     ("Claw Contraption", 13, day_13),
     ("Restroom Redoubt", 14, day_14),
 }
+
+pub use day_14::{robot_pictures, RobotPicture};
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::day;
+
+    #[test]
+    fn test_separate_phases() {
+        let day_2 = day(2);
+        let parsed = day_2.parse(example!(2, 1)).unwrap();
+        assert_eq!(parsed.day().day, 2);
+
+        // One parse can be shared by any number of part runs
+        for _ in 0..2 {
+            assert_eq!(day_2.part_1(&parsed), Ok("2".to_string()));
+            assert_eq!(day_2.part_2(&parsed), Ok("4".to_string()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Input parsed by day 2 passed to day 3")]
+    fn test_mismatched_handle() {
+        let parsed = day(2).parse(example!(2, 1)).unwrap();
+        let _ = day(3).part_1(&parsed);
+    }
+}
@@ -1,40 +1,840 @@
-use aoc_2024::{all_days, get_input, print_results_table};
-use clap::Parser;
-use std::path::PathBuf;
+use anyhow::Context;
+use aoc_2024::answers::{AnswerStatus, AnswerStore};
+use aoc_2024::bench::{bench_day, print_bench_table, BenchOptions};
+use aoc_2024::fetch::{FetchConfig, Fetcher, DEFAULT_BASE_URL, DEFAULT_YEAR};
+use aoc_2024::history::{self, print_comparison_table, HistoryEntry, Verdict};
+use aoc_2024::inputs::{input_set, InputCase};
+use aoc_2024::puzzle::{puzzle_dir, Puzzle};
+use aoc_2024::report::{write_results, Format};
+use aoc_2024::runner::{parallel_map, RunOptions};
+use aoc_2024::scaffold::NewDay;
+use aoc_2024::select::{DaySet, Parts, Selection};
+use aoc_2024::submit::{submit_answer, SubmitOutcome, WrongAnswers};
+use aoc_2024::tui::App;
+use aoc_2024::util::map2d::{recording, FrameFormat};
+use aoc_2024::watch::{self, write_diff_table};
+use aoc_2024::{all_days, robot_pictures, ErasedDay, Outcome, RunResult};
+use clap::{Parser, Subcommand};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Parse a non-negative, finite number of seconds from the command line
+fn parse_seconds(arg: &str) -> Result<Duration, String> {
+    let secs = arg.parse::<f64>().map_err(|err| err.to_string())?;
+    Duration::try_from_secs_f64(secs).map_err(|_| {
+        format!(
+            "Expected a finite, non-negative number of seconds, found {}",
+            arg
+        )
+    })
+}
 
 #[derive(Parser, Debug)]
 #[command(name = "aoc", about = "Joey9801's advent-of-code solutions")]
 struct Opt {
-    /// Only run the given day
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Only run the given days, e.g. "3-7,11"
     #[arg(name = "DAY", long = "day")]
-    day: Option<u8>,
+    day: Option<DaySet>,
+
+    /// Only run the given part of each day
+    #[arg(name = "PART", long = "part", value_parser = clap::value_parser!(u8).range(1..=2))]
+    part: Option<u8>,
+
+    /// Only run days whose name contains this, ignoring case. May be given more than once
+    #[arg(name = "NAME", long = "name")]
+    name: Vec<String>,
 
-    #[arg(name = "INPUT_ROOT", long = "input_root", default_value = "./inputs")]
+    /// Don't run the given days, e.g. "14" or "6,9-10"
+    #[arg(name = "EXCLUDE", long = "exclude")]
+    exclude: Option<DaySet>,
+
+    #[arg(
+        name = "INPUT_ROOT",
+        long = "input_root",
+        default_value = "./inputs",
+        global = true
+    )]
     input_root: PathBuf,
+
+    /// Site to fetch uncached inputs from
+    #[arg(
+        name = "BASE_URL",
+        long = "base_url",
+        default_value = DEFAULT_BASE_URL,
+        global = true
+    )]
+    base_url: String,
+
+    /// Puzzle year to fetch uncached inputs for
+    #[arg(name = "YEAR", long = "year", default_value_t = DEFAULT_YEAR, global = true)]
+    year: u16,
+
+    /// Run each day repeatedly and report timing statistics instead of a single run
+    #[arg(name = "BENCH", long = "bench")]
+    bench: bool,
+
+    /// Number of untimed runs of each day before sampling in bench mode
+    #[arg(name = "WARMUP", long = "warmup", default_value_t = 3)]
+    warmup: usize,
+
+    /// Maximum number of timed runs of each day in bench mode
+    #[arg(name = "SAMPLES", long = "samples", default_value_t = 100)]
+    samples: usize,
+
+    /// Stop sampling a day after this many seconds in bench mode
+    #[arg(name = "BENCH_TIME", long = "bench_time", value_parser = parse_seconds)]
+    bench_time: Option<Duration>,
+
+    /// Give up on any parse or part that runs for longer than this many seconds, or never if 0
+    #[arg(name = "TIMEOUT", long = "timeout", default_value = "60", value_parser = parse_seconds)]
+    timeout: Duration,
+
+    /// Number of days to run concurrently. Timings stay per-phase, but may be inflated by
+    /// contention between days
+    #[arg(
+        name = "JOBS",
+        long = "jobs",
+        default_value_t = 1,
+        conflicts_with = "BENCH"
+    )]
+    jobs: usize,
+
+    /// Also run part 1 and part 2 of each day concurrently
+    #[arg(
+        name = "PARALLEL_PARTS",
+        long = "parallel_parts",
+        conflicts_with = "BENCH"
+    )]
+    parallel_parts: bool,
+
+    /// Output format for the results table
+    #[arg(name = "FORMAT", long = "format", value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// File of known-correct answers to check results against, defaults to answers.toml in
+    /// INPUT_ROOT
+    #[arg(name = "ANSWERS", long = "answers", global = true)]
+    answers: Option<PathBuf>,
+
+    /// Store the results of this run as the known-correct answers
+    #[arg(name = "RECORD_ANSWERS", long = "record-answers")]
+    record_answers: bool,
+
+    /// File bench timings are appended to, defaults to history.jsonl in INPUT_ROOT
+    #[arg(name = "HISTORY", long = "history")]
+    history: Option<PathBuf>,
+
+    /// Don't append bench timings to the history
+    #[arg(name = "NO_HISTORY", long = "no_history")]
+    no_history: bool,
+
+    /// Bench each day, and compare the timings against those recorded for the given commit, or
+    /// "last" for the most recently recorded commit other than the current one
+    #[arg(
+        name = "COMPARE",
+        long = "compare",
+        conflicts_with_all = ["JOBS", "PARALLEL_PARTS"]
+    )]
+    compare: Option<String>,
+
+    /// Significance level for flagging a change in compare mode
+    #[arg(name = "ALPHA", long = "alpha", default_value_t = 0.05)]
+    alpha: f64,
+
+    /// Smallest relative change in mean time flagged in compare mode
+    #[arg(name = "MIN_CHANGE", long = "min_change", default_value_t = 0.05)]
+    min_change: f64,
+
+    /// Write the frames recorded by days that visualize themselves to this directory. Days run on
+    /// the calling thread without a timeout, and parts aren't run concurrently
+    #[arg(
+        name = "VISUALIZE",
+        long = "visualize",
+        conflicts_with_all = ["BENCH", "COMPARE"]
+    )]
+    visualize: Option<PathBuf>,
+
+    /// File format for visualized frames
+    #[arg(
+        name = "VISUALIZE_FORMAT",
+        long = "visualize_format",
+        value_enum,
+        default_value_t = FrameFormat::Apng
+    )]
+    visualize_format: FrameFormat,
+
+    /// Seconds between visualized frames in animated formats
+    #[arg(
+        name = "FRAME_DELAY",
+        long = "frame_delay",
+        default_value = "0.05",
+        value_parser = parse_seconds
+    )]
+    frame_delay: Duration,
+
+    /// Maximum number of frames written for each visualized day, longer solves are thinned out
+    #[arg(name = "MAX_FRAMES", long = "max_frames", default_value_t = 500)]
+    max_frames: usize,
+}
+
+impl Opt {
+    fn answers_path(&self) -> PathBuf {
+        self.answers
+            .clone()
+            .unwrap_or_else(|| self.input_root.join("answers.toml"))
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.history
+            .clone()
+            .unwrap_or_else(|| self.input_root.join("history.jsonl"))
+    }
+
+    /// Append entries to the history, unless disabled
+    fn record_history(&self, entries: &[HistoryEntry]) {
+        if !self.no_history {
+            history::append(&self.history_path(), entries).expect("Failed to record history");
+        }
+    }
+
+    fn run_options(&self) -> RunOptions {
+        RunOptions {
+            timeout: Some(self.timeout).filter(|timeout| !timeout.is_zero()),
+            parallel_parts: self.parallel_parts,
+            parts: self.part.map(Parts::only).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Download a day's puzzle description as Markdown, and save its examples for the day's tests
+    FetchPuzzle {
+        #[arg(name = "DAY", long = "day")]
+        day: u8,
+
+        /// Directory to save puzzle descriptions and examples in
+        #[arg(
+            name = "PUZZLE_ROOT",
+            long = "puzzle_root",
+            default_value = "./puzzles"
+        )]
+        puzzle_root: PathBuf,
+
+        /// Overwrite examples that have already been saved
+        #[arg(name = "OVERWRITE", long = "overwrite")]
+        overwrite: bool,
+    },
+
+    /// Generate a new day module from src/day_template.rs and register it in src/lib.rs
+    NewDay {
+        #[arg(name = "DAY")]
+        day: u8,
+
+        #[arg(name = "NAME")]
+        name: String,
+
+        /// File of example input to generate tests for, defaults to the first example saved by
+        /// fetch-puzzle if there is one
+        #[arg(name = "EXAMPLE", long = "example")]
+        example: Option<PathBuf>,
+
+        /// Expected part 1 answer for the example input
+        #[arg(name = "PART_1", long = "part_1")]
+        part_1: Option<String>,
+
+        /// Expected part 2 answer for the example input
+        #[arg(name = "PART_2", long = "part_2")]
+        part_2: Option<String>,
+
+        #[arg(name = "SRC_ROOT", long = "src_root", default_value = "./src")]
+        src_root: PathBuf,
+
+        #[arg(
+            name = "PUZZLE_ROOT",
+            long = "puzzle_root",
+            default_value = "./puzzles"
+        )]
+        puzzle_root: PathBuf,
+    },
+
+    /// Run one part of a day on its main input, and submit the answer to adventofcode.com
+    Submit {
+        #[arg(name = "DAY", long = "day")]
+        day: u8,
+
+        #[arg(name = "PART", long = "part", value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: u8,
+    },
+
+    /// Re-run the selected days whenever one of their inputs or saved examples changes, showing
+    /// how the results have changed. Select days before the subcommand, e.g. `--day 5 watch`
+    Watch {
+        /// Directory of puzzle examples to also run each day on
+        #[arg(
+            name = "PUZZLE_ROOT",
+            long = "puzzle_root",
+            default_value = "./puzzles"
+        )]
+        puzzle_root: PathBuf,
+
+        /// Source directory to watch, rebuilding and restarting when it changes
+        #[arg(name = "SRC_ROOT", long = "src_root", default_value = "./src")]
+        src_root: PathBuf,
+
+        /// How often to check for changes, in seconds
+        #[arg(
+            name = "INTERVAL",
+            long = "interval",
+            default_value = "0.5",
+            value_parser = parse_seconds
+        )]
+        interval: Duration,
+    },
+
+    /// Browse the selected days interactively, running days and parts on their main inputs and
+    /// comparing their answers and timings
+    Tui,
+
+    /// Save images of the day 14 robots after each of the given numbers of steps, or when they
+    /// form a picture of a Christmas tree if no steps are given
+    ExportRobots {
+        /// Numbers of steps to save images after, e.g. "0,100"
+        #[arg(name = "STEPS", long = "steps", value_delimiter = ',')]
+        steps: Vec<u64>,
+
+        #[arg(name = "OUTPUT_DIR", long = "output_dir", default_value = ".")]
+        output_dir: PathBuf,
+
+        #[arg(
+            name = "IMAGE_FORMAT",
+            long = "image_format",
+            default_value = "png",
+            value_parser = ["png", "ppm", "pbm"]
+        )]
+        image_format: String,
+    },
+}
+
+fn export_robots(
+    opt: &Opt,
+    fetcher: &Fetcher,
+    steps: &[u64],
+    output_dir: &Path,
+    image_format: &str,
+) -> anyhow::Result<()> {
+    let input = fetcher.cached_input(&opt.input_root, 14)?;
+    let pictures = robot_pictures(&input, steps)?;
+
+    if steps.is_empty() {
+        eprintln!("Found a picture after {} steps:", pictures[0].step);
+        eprint!("{}", pictures[0]);
+    }
+
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    for picture in pictures {
+        let path = output_dir.join(format!("day_14_step_{}.{}", picture.step, image_format));
+        picture
+            .frame()
+            .save(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn fetch_puzzle(
+    fetcher: &Fetcher,
+    day: u8,
+    puzzle_root: &Path,
+    overwrite: bool,
+) -> anyhow::Result<()> {
+    let puzzle = Puzzle::from_html(&fetcher.puzzle_page(day)?)?;
+    for path in puzzle.save(&puzzle_dir(puzzle_root, day), overwrite)? {
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn new_day(
+    day: u8,
+    name: &str,
+    example: Option<&Path>,
+    part_1: Option<&String>,
+    part_2: Option<&String>,
+    src_root: &Path,
+    puzzle_root: &Path,
+) -> anyhow::Result<()> {
+    let saved_example = puzzle_dir(puzzle_root, day).join("example_1.txt");
+    let example = match example {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        ),
+        None => std::fs::read_to_string(saved_example).ok(),
+    };
+
+    let new_day = NewDay {
+        day,
+        name: name.to_string(),
+        example,
+        part_1: part_1.cloned(),
+        part_2: part_2.cloned(),
+    };
+
+    for path in new_day.create(src_root, puzzle_root)? {
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn submit(opt: &Opt, fetcher: &Fetcher, day: u8, part: u8) -> anyhow::Result<()> {
+    let solution = all_days()
+        .into_iter()
+        .find(|d| d.name().day == day)
+        .with_context(|| format!("No solution for day {day}"))?;
+
+    let input = fetcher.cached_input(&opt.input_root, day)?;
+    let result = solution.run(&input, Parts::only(part));
+    let answer = match if part == 1 {
+        result.p1_result
+    } else {
+        result.p2_result
+    } {
+        Outcome::Answer(answer) => answer,
+        Outcome::Error(err) => anyhow::bail!("{}", err),
+        Outcome::Skipped => unreachable!("The submitted part is always run"),
+    };
+
+    let answers_path = opt.answers_path();
+    let mut answers = AnswerStore::load(&answers_path)?;
+    match answers.get(day).and_then(|answers| answers.part(part)) {
+        Some(known) if known == answer => {
+            println!("{answer} is already known to be correct");
+            return Ok(());
+        }
+        Some(known) => anyhow::bail!("Got {answer}, but the known correct answer is {known}"),
+        None => (),
+    }
+
+    let wrong_path = opt.input_root.join("wrong_answers.toml");
+    let mut wrong = WrongAnswers::load(&wrong_path)?;
+
+    println!("Submitting {answer} for day {day} part {part}");
+    let outcome = submit_answer(fetcher, &mut wrong, day, part, &answer)?;
+    wrong.save(&wrong_path)?;
+
+    match outcome {
+        SubmitOutcome::Correct => {
+            println!("{}", outcome);
+            answers.set(day, part, answer);
+            answers.save(&answers_path)
+        }
+        // Nothing went wrong, but the answer wasn't checked so it isn't recorded as correct
+        SubmitOutcome::AlreadySolved => {
+            println!("{}, {answer} wasn't checked", outcome);
+            Ok(())
+        }
+        _ => anyhow::bail!("{}", outcome),
+    }
+}
+
+/// Run each day on each of its inputs, checking the results against each input's expected
+/// answers, and storing them as the expected answers if requested
+///
+/// Returns the results, and whether any input failed to load.
+fn run_cases(
+    opt: &Opt,
+    fetcher: &Fetcher,
+    answers: &mut AnswerStore,
+    runs: Vec<(&dyn ErasedDay, InputCase)>,
+) -> (Vec<RunResult>, bool) {
+    let run_opts = opt.run_options();
+
+    let mut any_failed = false;
+    let mut answers_changed = false;
+    let mut results = Vec::with_capacity(runs.len());
+    let run_results = parallel_map(&runs, opt.jobs, |(d, case)| {
+        let input = case.load(fetcher, &opt.input_root, d.name().day)?;
+        let mut result = match &opt.visualize {
+            Some(dir) => visualize(opt, dir, *d, &case.label, &input)?,
+            None => d.run_isolated(&input, &run_opts),
+        };
+        result.input = case.label.clone();
+        anyhow::Ok(result)
+    });
+
+    for ((d, mut case), result) in runs.into_iter().zip(run_results) {
+        let mut result = match result {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Day {:02} [{}]: {:#}", d.name().day, case.label, err);
+                any_failed = true;
+                continue;
+            }
+        };
+
+        // Answers are only written when they change, so watch mode doesn't rewrite them on
+        // every run
+        if opt.record_answers {
+            let changed = case.expected.record(&result);
+            match case.answers_path() {
+                Some(path) if changed => case.expected.save(&path).expect("Failed to save answers"),
+                Some(_) => (),
+                None => answers_changed |= answers.record(&result),
+            }
+        }
+
+        case.check(&mut result);
+        results.push(result);
+    }
+
+    if answers_changed {
+        answers
+            .save(&opt.answers_path())
+            .expect("Failed to save answers");
+    }
+
+    (results, any_failed)
+}
+
+/// Run a day on the current thread, writing any frames it records under `dir`
+fn visualize(
+    opt: &Opt,
+    dir: &Path,
+    day: &dyn ErasedDay,
+    label: &str,
+    input: &str,
+) -> anyhow::Result<RunResult> {
+    let (result, frames) = recording(opt.max_frames, || day.run(input, opt.run_options().parts));
+    if frames.is_empty() {
+        return Ok(result);
+    }
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("day_{:02}_{}", day.name().day, label));
+    let written = opt
+        .visualize_format
+        .write(&frames, &path, opt.frame_delay)
+        .with_context(|| format!("Failed to write frames to {}", path.display()))?;
+    eprintln!("Wrote {} frames to {}", frames.len(), written.display());
+
+    Ok(result)
+}
+
+/// Report every error and answer that doesn't match the expected answer, returning whether
+/// there were any
+fn report_failures(results: &[RunResult]) -> bool {
+    let mut any_failed = false;
+    for result in results {
+        for err in result.errors() {
+            eprintln!("{} (input {})", err, result.input);
+            any_failed = true;
+        }
+
+        for (part, actual, status) in [
+            (1, &result.p1_result, &result.p1_status),
+            (2, &result.p2_result, &result.p2_status),
+        ] {
+            // Parts that failed to produce an answer have already been reported above
+            if let (Some(actual), Some(AnswerStatus::Wrong { expected })) =
+                (actual.answer(), status)
+            {
+                eprintln!(
+                    "Day {:02} [{}] part {}: got {}, expected {}",
+                    result.name.day, result.input, part, actual, expected
+                );
+                any_failed = true;
+            }
+        }
+    }
+    any_failed
+}
+
+/// Rebuild with cargo and replace this process with the new binary, keeping the same arguments
+///
+/// Only returns if the rebuild or restart failed.
+fn rebuild_and_restart() -> anyhow::Result<()> {
+    let mut cargo = std::process::Command::new("cargo");
+    cargo.arg("build");
+    if !cfg!(debug_assertions) {
+        cargo.arg("--release");
+    }
+
+    let features = [
+        ("alloc-profile", cfg!(feature = "alloc-profile")),
+        ("perf-counters", cfg!(feature = "perf-counters")),
+    ];
+    for (feature, enabled) in features {
+        if enabled {
+            cargo.args(["--features", feature]);
+        }
+    }
+
+    if !cargo.status().context("Failed to run cargo")?.success() {
+        anyhow::bail!("Build failed, still running the previous build");
+    }
+
+    let exe = std::env::current_exe().context("Failed to find the current executable")?;
+    let args = std::env::args_os().skip(1);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = std::process::Command::new(exe).args(args).exec();
+        Err(err).context("Failed to restart")
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (exe, args);
+        anyhow::bail!("Rebuilt, restart to run the new build")
+    }
+}
+
+fn watch(
+    opt: &Opt,
+    fetcher: &Fetcher,
+    solutions: &[Box<dyn ErasedDay>],
+    puzzle_root: &Path,
+    src_root: &Path,
+    interval: Duration,
+) -> anyhow::Result<()> {
+    if solutions.is_empty() {
+        anyhow::bail!("No solutions match CLI opts: {:?}", &opt);
+    }
+
+    let highlight = std::io::stdout().is_terminal();
+
+    let mut paths = solutions
+        .iter()
+        .flat_map(|d| watch::watched_paths(&opt.input_root, puzzle_root, d.name().day))
+        .collect::<Vec<_>>();
+    paths.push(src_root.to_path_buf());
+    let mut snapshot = watch::snapshot(&paths);
+
+    let mut previous = Vec::new();
+    loop {
+        let mut answers = AnswerStore::load(&opt.answers_path())?;
+        let mut runs = Vec::new();
+        for d in solutions {
+            let day = d.name().day;
+            let cases = input_set(&opt.input_root, day, &answers)?
+                .into_iter()
+                .chain(watch::example_cases(puzzle_root, day)?);
+            runs.extend(cases.map(|case| (d.as_ref(), case)));
+        }
+
+        let (results, _) = run_cases(opt, fetcher, &mut answers, runs);
+        write_diff_table(
+            &mut std::io::stdout().lock(),
+            &previous,
+            &results,
+            highlight,
+        )?;
+        report_failures(&results);
+        previous = results;
+
+        // Wait for a change, then for things to settle so a burst of writes causes a single run
+        let mut changed = Vec::new();
+        loop {
+            std::thread::sleep(interval);
+            let next = watch::snapshot(&paths);
+            let new_changes = watch::changed(&snapshot, &next);
+            if new_changes.is_empty() && !changed.is_empty() {
+                break;
+            }
+
+            changed.extend(new_changes);
+            snapshot = next;
+        }
+        changed.sort();
+        changed.dedup();
+
+        println!();
+        for path in &changed {
+            println!("Changed: {}", path.display());
+        }
+
+        if changed.iter().any(|path| path.starts_with(src_root)) {
+            println!("Rebuilding");
+            if let Err(err) = rebuild_and_restart() {
+                eprintln!("{:#}", err);
+            }
+        }
+    }
+}
+
+fn tui(opt: &Opt, fetcher: &Fetcher, solutions: Vec<Box<dyn ErasedDay>>) -> anyhow::Result<()> {
+    if solutions.is_empty() {
+        anyhow::bail!("No solutions match CLI opts: {:?}", &opt);
+    }
+
+    let answers = AnswerStore::load(&opt.answers_path())?;
+    let app = App::new(solutions, answers, |day, message| {
+        Ok(fetcher.cached_input_with(&opt.input_root, day, || {
+            *message = format!("Fetched input for day {}", day)
+        })?)
+    })
+    .with_timeout(opt.run_options().timeout);
+    Ok(aoc_2024::tui::run(app)?)
 }
 
 fn main() {
     let opt = Opt::parse();
+
+    let fetcher = Fetcher::new(FetchConfig {
+        base_url: opt.base_url.trim_end_matches('/').to_string(),
+        year: opt.year,
+        ..FetchConfig::from_env()
+    })
+    .expect("Failed to create HTTP client");
+
     let mut solutions = all_days();
 
-    if let Some(day) = &opt.day {
-        solutions = solutions
-            .drain(..)
-            .filter(|d| d.name().day == *day)
-            .collect();
+    let selection = Selection {
+        days: opt.day.clone(),
+        exclude: opt.exclude.clone(),
+        names: opt.name.clone(),
+    };
+    solutions.retain(|d| selection.matches(d.name()));
+    if let Some(command) = &opt.command {
+        let result = match command {
+            Command::FetchPuzzle {
+                day,
+                puzzle_root,
+                overwrite,
+            } => fetch_puzzle(&fetcher, *day, puzzle_root, *overwrite),
+            Command::NewDay {
+                day,
+                name,
+                example,
+                part_1,
+                part_2,
+                src_root,
+                puzzle_root,
+            } => new_day(
+                *day,
+                name,
+                example.as_deref(),
+                part_1.as_ref(),
+                part_2.as_ref(),
+                src_root,
+                puzzle_root,
+            ),
+            Command::Submit { day, part } => submit(&opt, &fetcher, *day, *part),
+            Command::Watch {
+                puzzle_root,
+                src_root,
+                interval,
+            } => watch(&opt, &fetcher, &solutions, puzzle_root, src_root, *interval),
+            Command::Tui => tui(&opt, &fetcher, solutions),
+            Command::ExportRobots {
+                steps,
+                output_dir,
+                image_format,
+            } => export_robots(&opt, &fetcher, steps, output_dir, image_format),
+        };
+
+        if let Err(err) = result {
+            eprintln!("{:#}", err);
+            std::process::exit(1);
+        }
+        return;
     }
 
+    let parts = opt.part.map(Parts::only).unwrap_or_default();
+
     if solutions.is_empty() {
         println!("No solutions match CLI opts: {:?}", &opt);
-    } else {
+    } else if opt.bench || opt.compare.is_some() {
+        let bench_opts = BenchOptions {
+            warmup: opt.warmup,
+            samples: opt.samples,
+            time_budget: opt.bench_time,
+            parts,
+        };
+
         let results = solutions
             .iter()
             .map(|d| {
-                let input = get_input(&opt.input_root, d.name()).expect("Failed to find an input");
-                d.run(&input)
+                let input = fetcher
+                    .cached_input(&opt.input_root, d.name().day)
+                    .expect("Failed to find an input");
+                bench_day(d.as_ref(), &input, &bench_opts)
+            })
+            .collect::<Vec<_>>();
+
+        print_bench_table(&results);
+
+        let commit = history::current_commit();
+        let timestamp = history::now_timestamp();
+        let entries = results
+            .iter()
+            .map(|result| HistoryEntry::from_bench(result, commit.clone(), timestamp))
+            .collect::<Vec<_>>();
+
+        let mut any_regressed = false;
+        if let Some(baseline) = &opt.compare {
+            let history = history::load(&opt.history_path()).expect("Failed to load history");
+            match history::baseline(&history, baseline, commit.as_deref()) {
+                Ok((baseline_commit, baseline)) => {
+                    println!();
+                    println!("Compared against {}", baseline_commit);
+                    let comparisons =
+                        history::compare(&baseline, &entries, opt.alpha, opt.min_change);
+                    print_comparison_table(&comparisons);
+
+                    for c in comparisons
+                        .iter()
+                        .filter(|c| c.verdict == Verdict::Regression)
+                    {
+                        eprintln!(
+                            "Day {:02} {} regressed by {:.1}%",
+                            c.day,
+                            c.phase,
+                            c.change() * 100.0
+                        );
+                        any_regressed = true;
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{:#}", err);
+                    any_regressed = true;
+                }
+            }
+        }
+
+        opt.record_history(&entries);
+        if any_regressed {
+            std::process::exit(1);
+        }
+    } else {
+        let mut answers = AnswerStore::load(&opt.answers_path()).expect("Failed to load answers");
+
+        let runs = solutions
+            .iter()
+            .flat_map(|d| {
+                let cases = input_set(&opt.input_root, d.name().day, &answers)
+                    .expect("Failed to find the day's inputs");
+                cases.into_iter().map(move |case| (d.as_ref(), case))
             })
             .collect::<Vec<_>>();
 
-        print_results_table(&results);
+        let (results, mut any_failed) = run_cases(&opt, &fetcher, &mut answers, runs);
+
+        write_results(&mut std::io::stdout().lock(), &results, opt.format)
+            .expect("Failed to write results");
+
+        any_failed |= report_failures(&results);
+
+        if any_failed {
+            std::process::exit(1);
+        }
     }
 }
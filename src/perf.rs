@@ -0,0 +1,268 @@
+use std::fmt::{Display, Formatter};
+
+/// Hardware performance counters for a single phase, counted on the thread the phase ran on
+///
+/// Only recorded on Linux when built with the `perf-counters` feature, and when the kernel lets
+/// us open the counters, which it often won't inside containers or VMs. Counts from any threads
+/// the phase spawns itself aren't included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct PerfCounts {
+    pub instructions: u64,
+    pub cycles: u64,
+    pub branch_misses: u64,
+    pub cache_misses: u64,
+}
+
+/// A count with a decimal unit, e.g. `1.5M`
+pub struct Count(pub u64);
+
+impl Display for Count {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["k", "M", "G", "T"];
+
+        if self.0 < 1000 {
+            return write!(f, "{}", self.0);
+        }
+
+        let mut value = self.0 as f64 / 1000.0;
+        let mut unit = 0;
+        while value >= 1000.0 && unit < UNITS.len() - 1 {
+            value /= 1000.0;
+            unit += 1;
+        }
+        write!(f, "{:.1}{}", value, UNITS[unit])
+    }
+}
+
+impl Display for PerfCounts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} / {} / {} / {}",
+            Count(self.instructions),
+            Count(self.cycles),
+            Count(self.branch_misses),
+            Count(self.cache_misses)
+        )
+    }
+}
+
+/// Run `f`, counting its instructions, cycles, branch misses and cache misses on the current
+/// thread if the counters are enabled and available
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<PerfCounts>) {
+    #[cfg(all(feature = "perf-counters", target_os = "linux"))]
+    {
+        let Some(group) = linux::CounterGroup::open() else {
+            return (f(), None);
+        };
+
+        group.enable();
+        let value = f();
+        (value, group.disable_and_read())
+    }
+
+    #[cfg(not(all(feature = "perf-counters", target_os = "linux")))]
+    {
+        (f(), None)
+    }
+}
+
+#[cfg(all(feature = "perf-counters", target_os = "linux"))]
+mod linux {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::PerfCounts;
+
+    /// The leading fields of the kernel's `perf_event_attr`, `PERF_ATTR_SIZE_VER0`
+    #[repr(C)]
+    #[derive(Default)]
+    struct PerfEventAttr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+    }
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+
+    const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+    const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+    const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+    const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+
+    const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const PERF_FORMAT_GROUP: u64 = 1 << 3;
+
+    const FLAG_DISABLED: u64 = 1 << 0;
+    const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_EVENT_IOC_ENABLE: u64 = 0x2400;
+    const PERF_EVENT_IOC_DISABLE: u64 = 0x2401;
+    const PERF_EVENT_IOC_RESET: u64 = 0x2403;
+    const PERF_IOC_FLAG_GROUP: libc::c_int = 1;
+
+    /// The counters in each group, in the order they're read back, the first leading the group
+    const EVENTS: [u64; 4] = [
+        PERF_COUNT_HW_INSTRUCTIONS,
+        PERF_COUNT_HW_CPU_CYCLES,
+        PERF_COUNT_HW_BRANCH_MISSES,
+        PERF_COUNT_HW_CACHE_MISSES,
+    ];
+
+    /// Set once opening the counters has failed, so we only try (and warn) once
+    static UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+    fn open_counter(config: u64, group_fd: libc::c_int) -> io::Result<OwnedFd> {
+        let is_leader = group_fd == -1;
+        let attr = PerfEventAttr {
+            kind: PERF_TYPE_HARDWARE,
+            size: std::mem::size_of::<PerfEventAttr>() as u32,
+            config,
+            read_format: PERF_FORMAT_GROUP
+                | PERF_FORMAT_TOTAL_TIME_ENABLED
+                | PERF_FORMAT_TOTAL_TIME_RUNNING,
+            // Only the leader starts disabled, the rest of the group follows it
+            flags: FLAG_EXCLUDE_KERNEL
+                | FLAG_EXCLUDE_HV
+                | if is_leader { FLAG_DISABLED } else { 0 },
+            ..PerfEventAttr::default()
+        };
+
+        // Counts the calling thread on whichever CPU it runs on
+        let pid: libc::pid_t = 0;
+        let cpu: libc::c_int = -1;
+        let flags: libc::c_ulong = 0;
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                pid,
+                cpu,
+                group_fd,
+                flags,
+            )
+        };
+
+        if fd < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            // Safety: the kernel just gave us this descriptor, nothing else owns it
+            Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+        }
+    }
+
+    /// A group of counters scheduled onto the CPU together, so their counts are comparable
+    pub struct CounterGroup {
+        counters: Vec<OwnedFd>,
+    }
+
+    impl CounterGroup {
+        /// Open a group counting the current thread, or `None` if the counters are unavailable
+        pub fn open() -> Option<Self> {
+            if UNAVAILABLE.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            match Self::try_open() {
+                Ok(group) => Some(group),
+                Err(err) => {
+                    if !UNAVAILABLE.swap(true, Ordering::Relaxed) {
+                        eprintln!("Hardware performance counters are unavailable ({err}), not recording them");
+                    }
+                    None
+                }
+            }
+        }
+
+        fn try_open() -> io::Result<Self> {
+            let mut counters: Vec<OwnedFd> = Vec::with_capacity(EVENTS.len());
+            for config in EVENTS {
+                let group_fd = counters.first().map_or(-1, |leader| leader.as_raw_fd());
+                counters.push(open_counter(config, group_fd)?);
+            }
+            Ok(Self { counters })
+        }
+
+        fn ioctl(&self, request: u64) -> bool {
+            let leader = self.counters[0].as_raw_fd();
+            unsafe { libc::ioctl(leader, request as _, PERF_IOC_FLAG_GROUP) == 0 }
+        }
+
+        pub fn enable(&self) {
+            // Failures show up as a group that never ran when it's read
+            self.ioctl(PERF_EVENT_IOC_RESET);
+            self.ioctl(PERF_EVENT_IOC_ENABLE);
+        }
+
+        /// Stop counting and read back the counts, `None` if the group never got to run
+        pub fn disable_and_read(&self) -> Option<PerfCounts> {
+            if !self.ioctl(PERF_EVENT_IOC_DISABLE) {
+                return None;
+            }
+
+            // The number of counters, the time enabled and running, then each counter's value
+            let mut buf = [0u64; 3 + EVENTS.len()];
+            let len = std::mem::size_of_val(&buf);
+            let read = unsafe {
+                libc::read(
+                    self.counters[0].as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    len,
+                )
+            };
+            if read != len as isize {
+                return None;
+            }
+
+            let [count, enabled, running, values @ ..] = buf;
+            if count != EVENTS.len() as u64 || running == 0 {
+                return None;
+            }
+
+            // Scale up if the group was multiplexed with other users of the counters
+            let scale = |value: u64| (value as f64 * enabled as f64 / running as f64) as u64;
+            let [instructions, cycles, branch_misses, cache_misses] = values.map(scale);
+
+            Some(PerfCounts {
+                instructions,
+                cycles,
+                branch_misses,
+                cache_misses,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        assert_eq!(Count(999).to_string(), "999");
+        assert_eq!(Count(1500).to_string(), "1.5k");
+        assert_eq!(Count(2_340_000).to_string(), "2.3M");
+    }
+
+    #[test]
+    fn test_measure() {
+        let (sum, counts) = measure(|| (0..10_000u64).map(std::hint::black_box).sum::<u64>());
+        assert_eq!(sum, 49_995_000);
+
+        // Counters are often unavailable, e.g. in CI containers, but should be sane if present
+        if let Some(counts) = counts {
+            assert!(counts.instructions >= 10_000);
+            assert!(counts.cycles > 0);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// A day's puzzle description, as extracted from its page on adventofcode.com
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    /// The description of each part that has been unlocked, converted to Markdown
+    pub markdown: String,
+
+    /// The contents of each `<pre><code>` block in the description, in page order
+    pub examples: Vec<String>,
+}
+
+/// Directory holding a day's description and examples, e.g. `puzzles/day_5/`
+pub fn puzzle_dir(puzzle_root: &Path, day: u8) -> PathBuf {
+    puzzle_root.join(format!("day_{}", day))
+}
+
+/// The inner HTML of each element in `html` opened by `open` and closed by `close`
+pub(crate) fn elements<'a>(html: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut elements = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find(open) {
+        let after_open = &rest[start + open.len()..];
+        let Some(tag_end) = after_open.find('>') else {
+            break;
+        };
+        let inner = &after_open[tag_end + 1..];
+        let Some(end) = inner.find(close) else {
+            break;
+        };
+
+        elements.push(&inner[..end]);
+        rest = &inner[end + close.len()..];
+    }
+
+    elements
+}
+
+/// Strip any tags from an HTML fragment and decode its character references
+pub(crate) fn text_content(html: &str) -> String {
+    let mut stripped = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => stripped.push(c),
+            _ => (),
+        }
+    }
+
+    let mut text = String::with_capacity(stripped.len());
+    let mut rest = stripped.as_str();
+    while let Some(start) = rest.find('&') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                entity => {
+                    let code = match entity.strip_prefix("#x") {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end + 1))
+        });
+
+        match decoded {
+            Some((c, len)) => {
+                text.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                text.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    text
+}
+
+impl Puzzle {
+    pub fn from_html(html: &str) -> anyhow::Result<Self> {
+        let articles = elements(html, "<article", "</article>");
+        if articles.is_empty() {
+            anyhow::bail!("No puzzle description found in the page");
+        }
+
+        let markdown = articles
+            .iter()
+            .map(|article| html2md::parse_html(article).trim().to_string())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let examples = articles
+            .iter()
+            .flat_map(|article| elements(article, "<pre", "</pre>"))
+            .map(|pre| match elements(pre, "<code", "</code>").first() {
+                Some(code) => text_content(code),
+                None => text_content(pre),
+            })
+            .collect();
+
+        Ok(Self {
+            markdown: markdown + "\n",
+            examples,
+        })
+    }
+
+    /// Save the description as `puzzle.md` and each example as `example_<n>.txt` in `dir`
+    ///
+    /// The description is always rewritten, as part 2 only appears after solving part 1.
+    /// Existing examples are kept unless `overwrite` is set, as day tests load them. Returns the
+    /// paths of the files written.
+    pub fn save(&self, dir: &Path, overwrite: bool) -> anyhow::Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut written = Vec::new();
+        let mut write = |path: PathBuf, contents: &str| {
+            std::fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(path);
+            anyhow::Ok(())
+        };
+
+        write(dir.join("puzzle.md"), &self.markdown)?;
+        for (idx, example) in self.examples.iter().enumerate() {
+            let path = dir.join(format!("example_{}.txt", idx + 1));
+            if overwrite || !path.exists() {
+                write(path, example)?;
+            }
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en-us">
+<body>
+<header><h1><a href="/">Advent of Code</a></h1></header>
+<main>
+<article class="day-desc"><h2>--- Day 3: Mull It Over ---</h2><p>It seems like the goal of the program is just to <em>multiply some numbers</em>.</p>
+<p>For example, consider the following section of corrupted memory:</p>
+<pre><code>x<em>mul(2,4)</em>%&amp;mul[3,7]!@^do_not_<em>mul(5,5)</em>+mul(32,64]then(<em>mul(11,8)mul(8,5)</em>)
+</code></pre>
+</article>
+<p>Your puzzle answer was <code>189600467</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2><p>For example:</p>
+<pre><code>x<em>mul(2,4)</em>&amp;mul[3,7]!^<em>don't()</em>_mul(5,5)+mul(32,64](mul(11,8)un<em>do()</em>?<em>mul(8,5)</em>)
+</code></pre>
+<p>This time, the sum of the results is <code>48</code> (<code>2*4 + 8*5</code>) &lt; 100.</p>
+</article>
+</main>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn test_examples() {
+        let puzzle = Puzzle::from_html(TEST_PAGE).unwrap();
+        assert_eq!(puzzle.examples, [example!(3, 1), example!(3, 2)]);
+    }
+
+    #[test]
+    fn test_markdown() {
+        let puzzle = Puzzle::from_html(TEST_PAGE).unwrap();
+        assert!(puzzle.markdown.contains("--- Day 3: Mull It Over ---"));
+        assert!(puzzle.markdown.contains("*multiply some numbers*"));
+        assert!(puzzle.markdown.contains("--- Part Two ---"));
+        assert!(puzzle.markdown.contains("```\nxmul(2,4)&mul[3,7]"));
+        assert!(!puzzle.markdown.contains("Your puzzle answer"));
+    }
+
+    #[test]
+    fn test_text_content() {
+        assert_eq!(
+            text_content("a<em>b</em>&lt;&#62;&#x41;&amp;&bogus;"),
+            "ab<>A&&bogus;"
+        );
+    }
+
+    #[test]
+    fn test_no_description() {
+        assert!(Puzzle::from_html("<html><body>404 Not Found</body></html>").is_err());
+    }
+}
@@ -0,0 +1,495 @@
+use std::io::Write;
+use std::time::Duration;
+
+use crate::answers::AnswerStatus;
+use crate::inputs::MAIN_INPUT;
+use crate::{Outcome, RunResult};
+
+/// Output formats for the results of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Aligned plain text table
+    Text,
+    /// JSON array with one object per day
+    Json,
+    /// CSV with a header row and one row per day
+    Csv,
+    /// The text table wrapped in a fenced code block, as pasted into README.md
+    Markdown,
+}
+
+pub(crate) fn serialize_nanos<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_nanos() as u64)
+}
+
+pub fn write_results(
+    w: &mut impl Write,
+    results: &[RunResult],
+    format: Format,
+) -> std::io::Result<()> {
+    match format {
+        Format::Text => write_results_table(w, results),
+        Format::Json => write_json(w, results),
+        Format::Csv => write_csv(w, results),
+        Format::Markdown => write_markdown(w, results),
+    }
+}
+
+/// A part's result, with a marker for whether it matches the stored answer if that was checked
+pub(crate) fn result_cell(result: &Outcome, status: &Option<AnswerStatus>) -> String {
+    match status {
+        Some(status) => format!("{} {}", result, status.marker()),
+        None => result.to_string(),
+    }
+}
+
+/// The day's name, followed by the input's label if it isn't the main input
+pub(crate) fn name_cell(result: &RunResult) -> String {
+    if result.input == MAIN_INPUT {
+        result.name.name.to_string()
+    } else {
+        format!("{} [{}]", result.name.name, result.input)
+    }
+}
+
+/// A measurement that's only made in some builds, or `-` if it wasn't made
+fn optional_cell(value: &Option<impl std::fmt::Display>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Columns following the total time, each a title and a cell for every result, for the
+/// measurements enabled by the `alloc-profile` and `perf-counters` features
+fn extra_columns(results: &[RunResult]) -> Vec<(&'static str, Vec<String>)> {
+    let column =
+        |title, cell: fn(&RunResult) -> String| (title, results.iter().map(cell).collect());
+
+    let mut columns = Vec::new();
+    if results.iter().any(RunResult::has_alloc_stats) {
+        // Allocation count / total bytes / peak bytes
+        columns.push(column("Parse allocs", |r| optional_cell(&r.parse_alloc)));
+        columns.push(column("P1 allocs", |r| optional_cell(&r.p1_alloc)));
+        columns.push(column("P2 allocs", |r| optional_cell(&r.p2_alloc)));
+    }
+    if results.iter().any(RunResult::has_counters) {
+        // Instructions / cycles / branch misses / cache misses
+        columns.push(column("Parse counters", |r| {
+            optional_cell(&r.parse_counters)
+        }));
+        columns.push(column("P1 counters", |r| optional_cell(&r.p1_counters)));
+        columns.push(column("P2 counters", |r| optional_cell(&r.p2_counters)));
+    }
+    columns
+}
+
+pub fn print_results_table(results: &[RunResult]) {
+    write_results_table(&mut std::io::stdout().lock(), results).expect("Failed to write results");
+}
+
+pub fn write_results_table(w: &mut impl Write, results: &[RunResult]) -> std::io::Result<()> {
+    write_table(w, results, true)
+}
+
+/// The results table, with the markers for checked answers left out unless `markers` is set
+fn write_table(w: &mut impl Write, results: &[RunResult], markers: bool) -> std::io::Result<()> {
+    if results.is_empty() {
+        return Ok(());
+    }
+
+    let result_cell = |result: &Outcome, status: &Option<AnswerStatus>| match markers {
+        true => result_cell(result, status),
+        false => result.to_string(),
+    };
+
+    fn col_width(results: &[RunResult], title: &str, len: impl Fn(&RunResult) -> usize) -> usize {
+        std::cmp::max(title.len(), results.iter().map(len).max().unwrap())
+    }
+
+    let name_width = col_width(results, "Name", |r| name_cell(r).chars().count());
+    let p1_result_width = col_width(results, "P1 result", |r| {
+        result_cell(&r.p1_result, &r.p1_status).chars().count()
+    });
+    let p2_result_width = col_width(results, "P2 result", |r| {
+        result_cell(&r.p2_result, &r.p2_status).chars().count()
+    });
+    let parse_time_width = col_width(results, "Parse time", |r| {
+        format!("{:?}", r.parse_time).len()
+    });
+    let p1_time_width = col_width(results, "P1 time", |r| format!("{:?}", r.p1_time).len());
+    let p2_time_width = col_width(results, "P2 time", |r| format!("{:?}", r.p2_time).len());
+
+    let total_parse_time: Duration = results.iter().map(|r| r.parse_time).sum();
+    let total_p1_time: Duration = results.iter().map(|r| r.p1_time).sum();
+    let total_p2_time: Duration = results.iter().map(|r| r.p2_time).sum();
+    let total_time: Duration = results.iter().map(|r| r.total_time()).sum();
+
+    let parse_time_width = std::cmp::max(parse_time_width, format!("{:?}", total_parse_time).len());
+    let p1_time_width = std::cmp::max(p1_time_width, format!("{:?}", total_p1_time).len());
+    let p2_time_width = std::cmp::max(p2_time_width, format!("{:?}", total_p2_time).len());
+
+    let total_offset = 5 // "day |"
+        + name_width + 3
+        + p1_result_width + 3
+        + p2_result_width + 1;
+
+    let header = format!(
+        "Day | {:name_width$} | {:p1_result_width$} | {:p2_result_width$} | {:parse_time_width$} | {:p1_time_width$} | {:p2_time_width$} | Total time",
+        "Name",
+        "P1 result",
+        "P2 result",
+        "Parse time",
+        "P1 time",
+        "P2 time",
+        name_width = name_width,
+        p1_result_width = p1_result_width,
+        p2_result_width = p2_result_width,
+        parse_time_width = parse_time_width,
+        p1_time_width = p1_time_width,
+        p2_time_width = p2_time_width,
+    );
+
+    let rows = results.iter().map(|result| {
+        format!("{:02}  | {:name_width$} | {:p1_result_width$} | {:p2_result_width$} | {:parse_time_width$?} | {:p1_time_width$?} | {:p2_time_width$?} | {:?}",
+            result.name.day,
+            name_cell(result),
+            result_cell(&result.p1_result, &result.p1_status),
+            result_cell(&result.p2_result, &result.p2_status),
+            result.parse_time,
+            result.p1_time,
+            result.p2_time,
+            result.total_time(),
+        )
+    }).collect::<Vec<_>>();
+
+    let extra_columns = extra_columns(results);
+    let (header, rows) = if extra_columns.is_empty() {
+        (header, rows)
+    } else {
+        let time_width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .chain([header.len()])
+            .max()
+            .unwrap();
+        let mut header = format!("{header:time_width$}");
+        let mut rows = rows
+            .iter()
+            .map(|row| format!("{row:time_width$}"))
+            .collect::<Vec<_>>();
+
+        for (idx, (title, cells)) in extra_columns.iter().enumerate() {
+            // The last column isn't padded, like the total time without any extra columns
+            let width = match idx == extra_columns.len() - 1 {
+                true => 0,
+                false => cells
+                    .iter()
+                    .map(String::len)
+                    .chain([title.len()])
+                    .max()
+                    .unwrap(),
+            };
+
+            header.push_str(&format!(" | {title:width$}"));
+            for (row, cell) in rows.iter_mut().zip(cells) {
+                row.push_str(&format!(" | {cell:width$}"));
+            }
+        }
+
+        (header, rows)
+    };
+
+    writeln!(w, "{}", header)?;
+    writeln!(w, "{}", "-".repeat(header.len()))?;
+
+    for row in rows {
+        writeln!(w, "{}", row)?;
+    }
+
+    writeln!(w, "{}", "-".repeat(header.len()))?;
+
+    writeln!(
+        w,
+        "{:>total_offset$} | {:parse_time_width$?} | {:p1_time_width$?} | {:p2_time_width$?} | {:?}",
+        "Total",
+        total_parse_time,
+        total_p1_time,
+        total_p2_time,
+        total_time,
+    )
+}
+
+fn write_json(w: &mut impl Write, results: &[RunResult]) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut *w, results)?;
+    writeln!(w)
+}
+
+/// Quote a CSV field if it contains any characters that would otherwise break the row
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+fn write_csv(w: &mut impl Write, results: &[RunResult]) -> std::io::Result<()> {
+    // Allocation and counter columns are only included when built with the features that
+    // measure them
+    let allocs = results.iter().any(RunResult::has_alloc_stats);
+    let counters = results.iter().any(RunResult::has_counters);
+
+    write!(
+        w,
+        "day,name,input,p1_result,p2_result,p1_check,p2_check,parse_time_ns,p1_time_ns,p2_time_ns,total_time_ns"
+    )?;
+    if allocs {
+        for phase in ["parse", "p1", "p2"] {
+            write!(w, ",{phase}_allocs,{phase}_alloc_bytes,{phase}_peak_bytes")?;
+        }
+    }
+    if counters {
+        for phase in ["parse", "p1", "p2"] {
+            write!(
+                w,
+                ",{phase}_instructions,{phase}_cycles,{phase}_branch_misses,{phase}_cache_misses"
+            )?;
+        }
+    }
+    writeln!(w)?;
+
+    let check = |status: &Option<AnswerStatus>| status.as_ref().map_or("", AnswerStatus::as_str);
+
+    for result in results {
+        write!(
+            w,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            result.name.day,
+            csv_field(result.name.name),
+            csv_field(&result.input),
+            csv_field(&result.p1_result.to_string()),
+            csv_field(&result.p2_result.to_string()),
+            check(&result.p1_status),
+            check(&result.p2_status),
+            result.parse_time.as_nanos(),
+            result.p1_time.as_nanos(),
+            result.p2_time.as_nanos(),
+            result.total_time().as_nanos(),
+        )?;
+        if allocs {
+            for stats in [&result.parse_alloc, &result.p1_alloc, &result.p2_alloc] {
+                match stats {
+                    Some(stats) => write!(
+                        w,
+                        ",{},{},{}",
+                        stats.allocations, stats.bytes, stats.peak_bytes
+                    )?,
+                    None => write!(w, ",,,")?,
+                }
+            }
+        }
+        if counters {
+            for counts in [
+                &result.parse_counters,
+                &result.p1_counters,
+                &result.p2_counters,
+            ] {
+                match counts {
+                    Some(counts) => write!(
+                        w,
+                        ",{},{},{},{}",
+                        counts.instructions,
+                        counts.cycles,
+                        counts.branch_misses,
+                        counts.cache_misses
+                    )?,
+                    None => write!(w, ",,,,")?,
+                }
+            }
+        }
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+fn write_markdown(w: &mut impl Write, results: &[RunResult]) -> std::io::Result<()> {
+    // Without answer markers, to match the table in the README
+    writeln!(w, "```text")?;
+    write_table(w, results, false)?;
+    writeln!(w, "```")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::AllocStats;
+    use crate::perf::PerfCounts;
+    use crate::DayName;
+
+    fn test_results() -> Vec<RunResult> {
+        vec![
+            RunResult {
+                name: DayName {
+                    name: "Historian Hysteria",
+                    day: 1,
+                },
+                input: MAIN_INPUT.to_string(),
+                parse_time: Duration::from_micros(150),
+                p1_time: Duration::from_micros(75),
+                p2_time: Duration::from_micros(170),
+                p1_result: Outcome::Answer("11".to_string()),
+                p2_result: Outcome::Answer("31".to_string()),
+                parse_alloc: None,
+                p1_alloc: None,
+                p2_alloc: None,
+                parse_counters: None,
+                p1_counters: None,
+                p2_counters: None,
+                p1_status: Some(AnswerStatus::Correct),
+                p2_status: Some(AnswerStatus::Wrong {
+                    expected: "32".to_string(),
+                }),
+            },
+            RunResult {
+                name: DayName {
+                    name: "Mull It Over",
+                    day: 3,
+                },
+                input: "example".to_string(),
+                parse_time: Duration::from_nanos(1500),
+                p1_time: Duration::from_nanos(20),
+                p2_time: Duration::from_nanos(30),
+                p1_result: Outcome::Answer("a,\"b\"".to_string()),
+                p2_result: Outcome::Answer("48".to_string()),
+                parse_alloc: None,
+                p1_alloc: None,
+                p2_alloc: None,
+                parse_counters: None,
+                p1_counters: None,
+                p2_counters: None,
+                p1_status: None,
+                p2_status: None,
+            },
+        ]
+    }
+
+    fn render(format: Format) -> String {
+        let mut out = Vec::new();
+        write_results(&mut out, &test_results(), format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            render(Format::Csv),
+            "day,name,input,p1_result,p2_result,p1_check,p2_check,parse_time_ns,p1_time_ns,p2_time_ns,total_time_ns\n\
+             1,Historian Hysteria,main,11,31,correct,wrong,150000,75000,170000,395000\n\
+             3,Mull It Over,example,\"a,\"\"b\"\"\",48,,,1500,20,30,1550\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json: serde_json::Value = serde_json::from_str(&render(Format::Json)).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({
+                "name": "Historian Hysteria",
+                "day": 1,
+                "input": "main",
+                "parse_time_ns": 150000,
+                "p1_time_ns": 75000,
+                "p2_time_ns": 170000,
+                "p1_result": "11",
+                "p2_result": "31",
+                "p1_check": { "status": "correct" },
+                "p2_check": { "status": "wrong", "expected": "32" },
+            })
+        );
+        assert!(json[1].get("p1_check").is_none());
+    }
+
+    #[test]
+    fn test_markdown() {
+        let markdown = render(Format::Markdown);
+        let text = render(Format::Text);
+
+        assert!(markdown.starts_with("```text\nDay | Name "));
+        assert!(markdown.ends_with("```\n"));
+        assert!(!markdown.contains(['✓', '✗', '?']));
+        assert!(markdown.contains("01  | Historian Hysteria     | 11        | 31        | "));
+
+        // The same table as for text, apart from the markers
+        let unmarked = text.replace(" ✓", "  ").replace(" ✗", "  ");
+        assert!(markdown.contains(&unmarked));
+    }
+
+    #[test]
+    fn test_text_markers() {
+        let text = render(Format::Text);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert!(lines[2].starts_with("01  | Historian Hysteria     | 11 ✓      | 31 ✗      | "));
+        assert!(lines[3].starts_with("03  | Mull It Over [example] | a,\"b\"     | 48        | "));
+        assert!(!lines[0].contains("allocs"));
+        assert!(!lines[0].contains("counters"));
+    }
+
+    #[test]
+    fn test_alloc_columns() {
+        let mut results = test_results();
+        results[0].p1_alloc = Some(AllocStats {
+            allocations: 12,
+            bytes: 1536,
+            peak_bytes: 512,
+        });
+
+        let mut out = Vec::new();
+        write_results(&mut out, &results, Format::Text).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with(" | Total time | Parse allocs | P1 allocs          | P2 allocs"));
+        assert!(lines[2].ends_with(" | -            | 12 / 1.5KiB / 512B | -"));
+        assert!(lines[3].ends_with(" | -            | -                  | -"));
+
+        let mut out = Vec::new();
+        write_results(&mut out, &results, Format::Csv).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with(",total_time_ns,parse_allocs,parse_alloc_bytes,parse_peak_bytes,p1_allocs,p1_alloc_bytes,p1_peak_bytes,p2_allocs,p2_alloc_bytes,p2_peak_bytes"));
+        assert!(lines[1].ends_with(",395000,,,,12,1536,512,,,"));
+    }
+
+    #[test]
+    fn test_counter_columns() {
+        let mut results = test_results();
+        results[1].parse_counters = Some(PerfCounts {
+            instructions: 12_500,
+            cycles: 8_000,
+            branch_misses: 25,
+            cache_misses: 3,
+        });
+
+        let mut out = Vec::new();
+        write_results(&mut out, &results, Format::Text).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert!(
+            lines[0].ends_with(" | Total time | Parse counters        | P1 counters | P2 counters")
+        );
+        assert!(lines[2].ends_with(" | -                     | -           | -"));
+        assert!(lines[3].ends_with(" | 12.5k / 8.0k / 25 / 3 | -           | -"));
+
+        let mut out = Vec::new();
+        write_results(&mut out, &results, Format::Csv).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with(",total_time_ns,parse_instructions,parse_cycles,parse_branch_misses,parse_cache_misses,p1_instructions,p1_cycles,p1_branch_misses,p1_cache_misses,p2_instructions,p2_cycles,p2_branch_misses,p2_cache_misses"));
+        assert!(lines[2].ends_with(",1550,12500,8000,25,3,,,,,,,,"));
+    }
+}
@@ -0,0 +1,233 @@
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::alloc::{self, AllocStats};
+use crate::perf::{self, PerfCounts};
+use crate::select::Parts;
+
+/// How a single phase (parse, part 1, or part 2) of a day finished
+#[derive(Debug)]
+pub enum PhaseOutput<T> {
+    Done(T),
+    Panic(String),
+    Timeout(Duration),
+}
+
+impl<T> PhaseOutput<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> PhaseOutput<U> {
+        match self {
+            PhaseOutput::Done(value) => PhaseOutput::Done(f(value)),
+            PhaseOutput::Panic(message) => PhaseOutput::Panic(message),
+            PhaseOutput::Timeout(timeout) => PhaseOutput::Timeout(timeout),
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic payload".to_string()
+    }
+}
+
+/// What was measured while running a single phase
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Measurement {
+    pub time: Duration,
+
+    /// Only measured when built with the `alloc-profile` feature
+    pub alloc: Option<AllocStats>,
+
+    /// Only measured when built with the `perf-counters` feature, if the counters are available
+    pub counters: Option<PerfCounts>,
+}
+
+impl Measurement {
+    fn time_only(time: Duration) -> Self {
+        Self {
+            time,
+            ..Self::default()
+        }
+    }
+}
+
+/// Run a phase on the current thread, measuring it and catching any panic
+pub fn run_phase<T>(f: impl FnOnce() -> T) -> (Measurement, PhaseOutput<T>) {
+    // Counters are opened and read outside of the timed section, as that takes a few syscalls
+    let ((time, (result, alloc)), counters) = perf::measure(|| {
+        let sw = Instant::now();
+        let result = alloc::measure(|| std::panic::catch_unwind(AssertUnwindSafe(f)));
+        (sw.elapsed(), result)
+    });
+    let measurement = Measurement {
+        time,
+        alloc,
+        counters,
+    };
+
+    match result {
+        Ok(value) => (measurement, PhaseOutput::Done(value)),
+        Err(payload) => (measurement, PhaseOutput::Panic(panic_message(payload))),
+    }
+}
+
+/// Options controlling how `ErasedDay::run_isolated` runs each phase
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Give up on any single phase that runs for longer than this
+    pub timeout: Option<Duration>,
+
+    /// Run part 1 and part 2 concurrently once the input has been parsed
+    pub parallel_parts: bool,
+
+    /// Which parts to run, any others are reported as skipped
+    pub parts: Parts,
+}
+
+/// The name of the worker threads isolated phases run on
+pub const PHASE_THREAD: &str = "aoc-phase";
+
+/// A phase running on its own worker thread
+pub struct PhaseHandle<T> {
+    rx: mpsc::Receiver<(Measurement, PhaseOutput<T>)>,
+    started: Instant,
+}
+
+impl<T: Send + 'static> PhaseHandle<T> {
+    pub fn spawn(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::Builder::new()
+            .name(PHASE_THREAD.to_string())
+            .spawn(move || {
+                // The receiver may have given up waiting already, nothing to do about that here
+                let _ = tx.send(run_phase(f));
+            })
+            .expect("Failed to spawn phase worker thread");
+
+        Self {
+            rx,
+            started: Instant::now(),
+        }
+    }
+
+    /// Wait for the phase to finish, giving up once `timeout` has passed since it was spawned
+    ///
+    /// A timed out phase can't be cancelled, so its thread is leaked and keeps running in the
+    /// background until the process exits.
+    pub fn wait(self, timeout: Option<Duration>) -> (Measurement, PhaseOutput<T>) {
+        let disconnected =
+            || PhaseOutput::Panic("Phase worker exited without a result".to_string());
+
+        let received = match timeout {
+            Some(timeout) => {
+                let remaining = timeout.saturating_sub(self.started.elapsed());
+                self.rx.recv_timeout(remaining).map_err(|err| match err {
+                    mpsc::RecvTimeoutError::Timeout => PhaseOutput::Timeout(timeout),
+                    mpsc::RecvTimeoutError::Disconnected => disconnected(),
+                })
+            }
+            None => self.rx.recv().map_err(|_| disconnected()),
+        };
+
+        match received {
+            Ok(measured_output) => measured_output,
+            Err(PhaseOutput::Timeout(timeout)) => (
+                Measurement::time_only(timeout),
+                PhaseOutput::Timeout(timeout),
+            ),
+            Err(output) => (Measurement::default(), output),
+        }
+    }
+}
+
+/// Run a phase on its own worker thread, giving up on it after `timeout`
+pub fn run_phase_isolated<T: Send + 'static>(
+    timeout: Option<Duration>,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> (Measurement, PhaseOutput<T>) {
+    PhaseHandle::spawn(f).wait(timeout)
+}
+
+/// Map `f` over `items` using up to `jobs` worker threads, keeping the results in order
+pub fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(idx) else {
+                    break;
+                };
+
+                let result = f(item);
+                results.lock().unwrap()[idx] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("Every item is mapped exactly once"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_phase_panic() {
+        let (_, output) = run_phase(|| -> u64 { panic!("Oh no {}", 5) });
+        assert!(matches!(output, PhaseOutput::Panic(message) if message == "Oh no 5"));
+
+        let (_, output) = run_phase(|| 5);
+        assert!(matches!(output, PhaseOutput::Done(5)));
+    }
+
+    #[test]
+    fn test_run_phase_isolated() {
+        let (_, output) = run_phase_isolated(Some(Duration::from_secs(10)), || 5);
+        assert!(matches!(output, PhaseOutput::Done(5)));
+
+        let (_, output) = run_phase_isolated(None, || -> u64 { panic!("Oh no") });
+        assert!(matches!(output, PhaseOutput::Panic(message) if message == "Oh no"));
+
+        let timeout = Duration::from_millis(10);
+        let (measurement, output) = run_phase_isolated(Some(timeout), || -> u64 {
+            loop {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        });
+        assert!(matches!(output, PhaseOutput::Timeout(t) if t == timeout));
+        assert_eq!(measurement.time, timeout);
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        let items = (0..100).collect::<Vec<u64>>();
+        let expected = items.iter().map(|x| x * x).collect::<Vec<_>>();
+
+        assert_eq!(parallel_map(&items, 1, |x| x * x), expected);
+        assert_eq!(parallel_map(&items, 8, |x| x * x), expected);
+        assert!(parallel_map(&[] as &[u64], 8, |x| x * x).is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::puzzle::puzzle_dir;
+
+/// Starting point for each new day module
+pub const DAY_TEMPLATE: &str = include_str!("day_template.rs");
+
+const DEFINE_DAYS: &str = "define_days! {\n";
+
+#[derive(Debug, Clone, Default)]
+pub struct NewDay {
+    pub day: u8,
+    pub name: String,
+
+    /// Example input for the generated tests
+    pub example: Option<String>,
+
+    /// Expected answers for the example input
+    pub part_1: Option<String>,
+    pub part_2: Option<String>,
+}
+
+/// An expected answer as a Rust literal, numeric if it looks like a number
+fn answer_literal(answer: &str) -> String {
+    match answer.parse::<i64>() {
+        Ok(answer) => answer.to_string(),
+        Err(_) => format!("{:?}", answer),
+    }
+}
+
+impl NewDay {
+    /// Source for the new day's module, the template followed by a test module for its example
+    pub fn render_module(&self) -> String {
+        let mut module = DAY_TEMPLATE.to_string();
+        if self.example.is_none() {
+            return module;
+        }
+
+        let day = self.day;
+        writeln!(module).unwrap();
+        writeln!(module, "#[cfg(test)]").unwrap();
+        writeln!(module, "mod tests {{").unwrap();
+        writeln!(module, "    use super::*;").unwrap();
+        writeln!(module).unwrap();
+        writeln!(module, "    const TEST_INPUT: &str = example!({day}, 1);").unwrap();
+
+        let tests = [
+            ("test_part_1", "solve_part_1", &self.part_1),
+            ("test_part_2", "solve_part_2", &self.part_2),
+        ];
+        let mut any_answers = false;
+        for (test, solve, answer) in tests {
+            let Some(answer) = answer else {
+                continue;
+            };
+            any_answers = true;
+
+            writeln!(module).unwrap();
+            writeln!(module, "    #[test]").unwrap();
+            writeln!(module, "    fn {test}() {{").unwrap();
+            writeln!(module, "        let input = parse(TEST_INPUT).unwrap();").unwrap();
+            writeln!(
+                module,
+                "        assert_eq!({solve}(&input), {});",
+                answer_literal(answer)
+            )
+            .unwrap();
+            writeln!(module, "    }}").unwrap();
+        }
+
+        if !any_answers {
+            writeln!(module).unwrap();
+            writeln!(module, "    #[test]").unwrap();
+            writeln!(module, "    fn test_parse() {{").unwrap();
+            writeln!(module, "        parse(TEST_INPUT).unwrap();").unwrap();
+            writeln!(module, "    }}").unwrap();
+        }
+
+        writeln!(module, "}}").unwrap();
+        module
+    }
+
+    /// Add this day to the `define_days!` invocation in the source of `lib.rs`, keeping the days
+    /// in order
+    pub fn register(&self, lib_rs: &str) -> anyhow::Result<String> {
+        let start = lib_rs
+            .find(DEFINE_DAYS)
+            .context("No define_days! invocation found")?
+            + DEFINE_DAYS.len();
+        let len = lib_rs[start..]
+            .find("\n}")
+            .context("Unterminated define_days! invocation")?
+            + 1;
+
+        let mut entries = lib_rs[start..start + len].lines().collect::<Vec<_>>();
+        let entry_day =
+            |entry: &str| -> Option<u8> { entry.split(',').nth(1)?.trim().parse().ok() };
+
+        if entries
+            .iter()
+            .any(|entry| entry_day(entry) == Some(self.day))
+        {
+            anyhow::bail!("Day {} is already registered", self.day);
+        }
+
+        let new_entry = format!("    ({:?}, {}, day_{}),", self.name, self.day, self.day);
+        let idx = entries
+            .iter()
+            .position(|entry| entry_day(entry).is_some_and(|day| day > self.day))
+            .unwrap_or(entries.len());
+        entries.insert(idx, &new_entry);
+
+        let mut registered = lib_rs[..start].to_string();
+        for entry in entries {
+            registered.push_str(entry);
+            registered.push('\n');
+        }
+        registered.push_str(&lib_rs[start + len..]);
+
+        Ok(registered)
+    }
+
+    /// Write the new day's module and example, and register it in `lib.rs`
+    ///
+    /// Returns the paths of the files written.
+    pub fn create(&self, src_root: &Path, puzzle_root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let module_path = src_root.join(format!("day_{}.rs", self.day));
+        if module_path.exists() {
+            anyhow::bail!("{} already exists", module_path.display());
+        }
+
+        let lib_path = src_root.join("lib.rs");
+        let lib_rs = std::fs::read_to_string(&lib_path)
+            .with_context(|| format!("Failed to read {}", lib_path.display()))?;
+        let lib_rs = self.register(&lib_rs)?;
+
+        let mut written = Vec::new();
+        if let Some(example) = &self.example {
+            let dir = puzzle_dir(puzzle_root, self.day);
+            let example_path = dir.join("example_1.txt");
+
+            match std::fs::read_to_string(&example_path) {
+                Ok(existing) if existing == *example => (),
+                Ok(_) => anyhow::bail!(
+                    "{} already exists with a different example",
+                    example_path.display()
+                ),
+                Err(_) => {
+                    std::fs::create_dir_all(&dir)
+                        .with_context(|| format!("Failed to create {}", dir.display()))?;
+                    std::fs::write(&example_path, example)
+                        .with_context(|| format!("Failed to write {}", example_path.display()))?;
+                    written.push(example_path);
+                }
+            }
+        }
+
+        for (path, contents) in [(module_path, self.render_module()), (lib_path, lib_rs)] {
+            std::fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_LIB: &str = r#"mod util;
+
+define_days! {
+    ("Historian Hysteria", 1, day_1),
+    ("Claw Contraption", 13, day_13),
+}
+"#;
+
+    fn new_day(day: u8) -> NewDay {
+        NewDay {
+            day,
+            name: "Garden Groups".to_string(),
+            ..NewDay::default()
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        assert_eq!(
+            new_day(12).register(TEST_LIB).unwrap(),
+            r#"mod util;
+
+define_days! {
+    ("Historian Hysteria", 1, day_1),
+    ("Garden Groups", 12, day_12),
+    ("Claw Contraption", 13, day_13),
+}
+"#
+        );
+
+        assert!(new_day(20).register(TEST_LIB).unwrap().ends_with(
+            "(\"Claw Contraption\", 13, day_13),\n    (\"Garden Groups\", 20, day_20),\n}\n"
+        ));
+        assert!(new_day(13).register(TEST_LIB).is_err());
+        assert!(new_day(12).register("mod util;\n").is_err());
+    }
+
+    #[test]
+    fn test_render_module() {
+        assert_eq!(new_day(12).render_module(), DAY_TEMPLATE);
+
+        let module = NewDay {
+            example: Some("AAAA\n".to_string()),
+            part_1: Some("140".to_string()),
+            part_2: Some("a,b".to_string()),
+            ..new_day(12)
+        }
+        .render_module();
+
+        assert!(module.starts_with(DAY_TEMPLATE));
+        assert!(module.contains("    const TEST_INPUT: &str = example!(12, 1);\n"));
+        assert!(module.contains("        assert_eq!(solve_part_1(&input), 140);\n"));
+        assert!(module.contains("        assert_eq!(solve_part_2(&input), \"a,b\");\n"));
+        assert!(!module.contains("test_parse"));
+
+        let module = NewDay {
+            example: Some("AAAA\n".to_string()),
+            ..new_day(12)
+        }
+        .render_module();
+        assert!(module.contains("fn test_parse()"));
+        assert!(!module.contains("test_part_1"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::DayName;
+
+/// A set of days, given on the command line as a comma separated list of days and inclusive
+/// ranges of days, e.g. `3-7,11`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaySet(Vec<RangeInclusive<u8>>);
+
+impl DaySet {
+    pub fn contains(&self, day: u8) -> bool {
+        self.0.iter().any(|range| range.contains(&day))
+    }
+}
+
+impl FromStr for DaySet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_day = |day: &str| {
+            day.trim()
+                .parse::<u8>()
+                .map_err(|_| format!("Invalid day {:?}", day.trim()))
+        };
+
+        let mut ranges = Vec::new();
+        for item in s.split(',') {
+            let range = match item.split_once('-') {
+                Some((start, end)) => parse_day(start)?..=parse_day(end)?,
+                None => {
+                    let day = parse_day(item)?;
+                    day..=day
+                }
+            };
+
+            if range.is_empty() {
+                return Err(format!("Empty day range {:?}", item.trim()));
+            }
+            ranges.push(range);
+        }
+
+        Ok(Self(ranges))
+    }
+}
+
+/// Which parts of each selected day to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parts {
+    pub part_1: bool,
+    pub part_2: bool,
+}
+
+impl Parts {
+    /// Only run the given part, which must be 1 or 2
+    pub fn only(part: u8) -> Self {
+        assert!(matches!(part, 1 | 2), "Invalid part {part}");
+        Self {
+            part_1: part == 1,
+            part_2: part == 2,
+        }
+    }
+}
+
+impl Default for Parts {
+    fn default() -> Self {
+        Self {
+            part_1: true,
+            part_2: true,
+        }
+    }
+}
+
+/// Which days to run, built from the command line options
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Only run these days, or every day if `None`
+    pub days: Option<DaySet>,
+
+    /// Never run these days, even if they're otherwise selected
+    pub exclude: Option<DaySet>,
+
+    /// Only run days whose name contains one of these, ignoring case. Every day matches if empty
+    pub names: Vec<String>,
+}
+
+impl Selection {
+    pub fn matches(&self, name: DayName) -> bool {
+        let day_name = name.name.to_lowercase();
+
+        self.days
+            .as_ref()
+            .is_none_or(|days| days.contains(name.day))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|exclude| exclude.contains(name.day))
+            && (self.names.is_empty()
+                || self
+                    .names
+                    .iter()
+                    .any(|pattern| day_name.contains(&pattern.to_lowercase())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(name: &'static str, day: u8) -> DayName {
+        DayName { name, day }
+    }
+
+    #[test]
+    fn test_parse_day_set() {
+        let days: DaySet = "3-7,11".parse().unwrap();
+        assert_eq!(days, DaySet(vec![3..=7, 11..=11]));
+        assert!(days.contains(3) && days.contains(7) && days.contains(11));
+        assert!(!days.contains(2) && !days.contains(8));
+
+        assert_eq!("5".parse::<DaySet>(), Ok(DaySet(vec![5..=5])));
+        assert!("7-3".parse::<DaySet>().is_err());
+        assert!("3,x".parse::<DaySet>().is_err());
+        assert!("".parse::<DaySet>().is_err());
+    }
+
+    #[test]
+    fn test_selection() {
+        let everything = Selection::default();
+        assert!(everything.matches(day("Garden Groups", 12)));
+
+        let selection = Selection {
+            days: Some("1-13".parse().unwrap()),
+            exclude: Some("4".parse().unwrap()),
+            names: vec!["garden".to_string(), "SEARCH".to_string()],
+        };
+        assert!(selection.matches(day("Garden Groups", 12)));
+        assert!(!selection.matches(day("Ceres Search", 4)));
+        assert!(!selection.matches(day("Restroom Redoubt", 14)));
+        assert!(!selection.matches(day("Historian Hysteria", 1)));
+    }
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(
+            Parts::only(2),
+            Parts {
+                part_1: false,
+                part_2: true
+            }
+        );
+        assert!(Parts::default().part_1 && Parts::default().part_2);
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::answers::{days_to_toml, parse_days};
+use crate::fetch::{FetchError, Fetcher};
+use crate::puzzle::{elements, text_content};
+
+/// How adventofcode.com responded to a submitted answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    Correct,
+    TooHigh,
+    TooLow,
+
+    /// Wrong, without a hint as to which direction
+    Wrong,
+
+    /// Submitted too soon after a previous answer, the answer wasn't checked
+    RateLimited {
+        wait: Option<Duration>,
+    },
+
+    /// The part has already been solved, the answer wasn't checked
+    AlreadySolved,
+
+    /// Part 2 was submitted before part 1 has been solved, the answer wasn't checked
+    Part1Unsolved,
+}
+
+impl SubmitOutcome {
+    /// Parse the response page from submitting an answer
+    pub fn from_html(html: &str) -> anyhow::Result<Self> {
+        let message = elements(html, "<article", "</article>")
+            .first()
+            .map(|article| text_content(article))
+            .context("No message found in the response")?;
+
+        let outcome = if message.contains("That's the right answer") {
+            Self::Correct
+        } else if message.contains("That's not the right answer") {
+            if message.contains("too high") {
+                Self::TooHigh
+            } else if message.contains("too low") {
+                Self::TooLow
+            } else {
+                Self::Wrong
+            }
+        } else if message.contains("You gave an answer too recently") {
+            Self::RateLimited {
+                wait: parse_wait(&message),
+            }
+        } else if message.contains("You don't seem to be solving the right level") {
+            // The same response is given for part 2 while part 1 is unsolved, which
+            // `submit_answer` tells apart
+            Self::AlreadySolved
+        } else {
+            anyhow::bail!("Unrecognised response: {}", message.trim());
+        };
+
+        Ok(outcome)
+    }
+
+    pub fn is_wrong(&self) -> bool {
+        matches!(self, Self::TooHigh | Self::TooLow | Self::Wrong)
+    }
+}
+
+impl std::fmt::Display for SubmitOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Correct => write!(f, "Correct"),
+            Self::TooHigh => write!(f, "Wrong, too high"),
+            Self::TooLow => write!(f, "Wrong, too low"),
+            Self::Wrong => write!(f, "Wrong"),
+            Self::RateLimited { wait: Some(wait) } => {
+                write!(f, "Rate limited, try again in {:?}", wait)
+            }
+            Self::RateLimited { wait: None } => write!(f, "Rate limited, try again later"),
+            Self::AlreadySolved => write!(f, "Already solved"),
+            Self::Part1Unsolved => write!(f, "Part 1 hasn't been solved yet, submit it first"),
+        }
+    }
+}
+
+/// The wait in a message like "You have 1m 5s left to wait."
+fn parse_wait(message: &str) -> Option<Duration> {
+    let start = message.find("You have ")? + "You have ".len();
+    let end = start + message[start..].find(" left to wait")?;
+
+    let mut secs = 0;
+    for part in message[start..end].split_whitespace() {
+        let (value, unit) = part.split_at(part.find(|c: char| !c.is_ascii_digit())?);
+        let value: u64 = value.parse().ok()?;
+        secs += match unit {
+            "h" => value * 3600,
+            "m" => value * 60,
+            "s" => value,
+            _ => return None,
+        };
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct WrongPartAnswers {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    part_1: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    part_2: Vec<String>,
+}
+
+/// Answers that have been submitted and rejected, stored as TOML in the form:
+///
+/// ```toml
+/// [day_05]
+/// part_2 = ["4076", "4078"]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WrongAnswers {
+    days: BTreeMap<u8, WrongPartAnswers>,
+}
+
+impl WrongAnswers {
+    /// Load the record from the given path, treating a missing file as an empty record
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let days =
+            parse_days(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self { days })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, days_to_toml(&self.days))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn part(&self, day: u8, part: u8) -> &[String] {
+        match (self.days.get(&day), part) {
+            (Some(answers), 1) => &answers.part_1,
+            (Some(answers), 2) => &answers.part_2,
+            _ => &[],
+        }
+    }
+
+    pub fn contains(&self, day: u8, part: u8, answer: &str) -> bool {
+        self.part(day, part).iter().any(|wrong| wrong == answer)
+    }
+
+    pub fn insert(&mut self, day: u8, part: u8, answer: String) {
+        if self.contains(day, part, &answer) {
+            return;
+        }
+
+        let answers = self.days.entry(day).or_default();
+        match part {
+            1 => answers.part_1.push(answer),
+            2 => answers.part_2.push(answer),
+            _ => panic!("Invalid part {part}"),
+        }
+    }
+}
+
+impl Fetcher {
+    /// Post an answer for the given part, returning the response page
+    ///
+    /// Never retried, as a request that failed on the way back may still have been counted.
+    pub fn post_answer(&self, day: u8, part: u8, answer: &str) -> Result<String, FetchError> {
+        let url = self.url(&format!("day/{day}/answer"));
+        let form = [("level", part.to_string()), ("answer", answer.to_string())];
+        self.send_with_retries(&url, 0, |client| client.post(&url).form(&form))
+    }
+}
+
+/// Submit an answer, refusing to resubmit one already known to be wrong, and recording it in
+/// `wrong` if it's rejected
+pub fn submit_answer(
+    fetcher: &Fetcher,
+    wrong: &mut WrongAnswers,
+    day: u8,
+    part: u8,
+    answer: &str,
+) -> anyhow::Result<SubmitOutcome> {
+    if wrong.contains(day, part, answer) {
+        anyhow::bail!("{answer} has already been rejected for day {day} part {part}");
+    }
+
+    let mut outcome = SubmitOutcome::from_html(&fetcher.post_answer(day, part, answer)?)?;
+
+    // Part 2 is only described on the puzzle page once part 1 has been solved
+    if outcome == SubmitOutcome::AlreadySolved && part == 2 {
+        let page = fetcher.puzzle_page(day)?;
+        if elements(&page, "<article", "</article>").len() < 2 {
+            outcome = SubmitOutcome::Part1Unsolved;
+        }
+    }
+
+    if outcome.is_wrong() {
+        wrong.insert(day, part, answer.to_string());
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::{stand_in, FetchConfig};
+
+    fn response(message: &str) -> String {
+        format!("<html><body><main>\n<article><p>{message}</p></article>\n</main></body></html>")
+    }
+
+    #[test]
+    fn test_outcome() {
+        let outcome = |message| SubmitOutcome::from_html(&response(message)).unwrap();
+
+        assert_eq!(
+            outcome("That's the right answer!  You are <em>one gold star</em> closer."),
+            SubmitOutcome::Correct
+        );
+        assert_eq!(
+            outcome("That's not the right answer; your answer is too high.  If you're stuck..."),
+            SubmitOutcome::TooHigh
+        );
+        assert_eq!(
+            outcome("That's not the right answer; your answer is too low."),
+            SubmitOutcome::TooLow
+        );
+        assert_eq!(
+            outcome("That's not the right answer.  If you're stuck, make sure..."),
+            SubmitOutcome::Wrong
+        );
+        assert_eq!(
+            outcome("You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 1m 5s left to wait. [<a href=\"/2024/day/5\">Return to Day 5</a>]"),
+            SubmitOutcome::RateLimited {
+                wait: Some(Duration::from_secs(65))
+            }
+        );
+        assert_eq!(
+            outcome("You don't seem to be solving the right level.  Did you already complete it?"),
+            SubmitOutcome::AlreadySolved
+        );
+        assert!(SubmitOutcome::from_html(&response("Something new")).is_err());
+        assert!(SubmitOutcome::from_html("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_wrong_answers_roundtrip() {
+        let mut wrong = WrongAnswers::default();
+        wrong.insert(5, 2, "4076".to_string());
+        wrong.insert(5, 2, "4078".to_string());
+        wrong.insert(5, 2, "4076".to_string());
+
+        let toml = days_to_toml(&wrong.days);
+        assert_eq!(toml, "[day_05]\npart_2 = [\"4076\", \"4078\"]\n");
+        assert_eq!(
+            WrongAnswers {
+                days: parse_days(&toml).unwrap()
+            },
+            wrong
+        );
+    }
+
+    #[test]
+    fn test_submit() {
+        let (base_url, requests) = stand_in::serve(vec![(
+            200,
+            "<main><article><p>That's not the right answer; your answer is too low.</p></article></main>",
+        )]);
+        let fetcher = Fetcher::new(FetchConfig {
+            base_url,
+            session: Some("abc123".to_string()),
+            min_interval: Duration::ZERO,
+            ..FetchConfig::default()
+        })
+        .unwrap();
+        let mut wrong = WrongAnswers::default();
+
+        let outcome = submit_answer(&fetcher, &mut wrong, 5, 2, "4076").unwrap();
+        assert_eq!(outcome, SubmitOutcome::TooLow);
+        assert!(wrong.contains(5, 2, "4076"));
+
+        let request = requests.recv().unwrap();
+        assert_eq!(request.request_line, "POST /2024/day/5/answer HTTP/1.1");
+        assert_eq!(request.header("cookie"), Some("session=abc123"));
+        assert_eq!(request.body, "level=2&answer=4076");
+
+        // Refused locally, the stand-in would refuse a second connection
+        assert!(submit_answer(&fetcher, &mut wrong, 5, 2, "4076").is_err());
+    }
+
+    #[test]
+    fn test_submit_wrong_level() {
+        const WRONG_LEVEL: &str = "<main><article><p>You don't seem to be solving the right \
+            level.  Did you already complete it?</p></article></main>";
+        let (base_url, requests) = stand_in::serve(vec![
+            (200, WRONG_LEVEL),
+            (
+                200,
+                "<main><article><h2>--- Day 5 ---</h2></article></main>",
+            ),
+            (200, WRONG_LEVEL),
+            (
+                200,
+                "<main><article><h2>--- Day 5 ---</h2></article>\
+                 <article><h2>--- Part Two ---</h2></article></main>",
+            ),
+        ]);
+        let fetcher = Fetcher::new(FetchConfig {
+            base_url,
+            session: Some("abc123".to_string()),
+            min_interval: Duration::ZERO,
+            ..FetchConfig::default()
+        })
+        .unwrap();
+        let mut wrong = WrongAnswers::default();
+
+        let outcome = submit_answer(&fetcher, &mut wrong, 5, 2, "4076").unwrap();
+        assert_eq!(outcome, SubmitOutcome::Part1Unsolved);
+        let outcome = submit_answer(&fetcher, &mut wrong, 5, 2, "4076").unwrap();
+        assert_eq!(outcome, SubmitOutcome::AlreadySolved);
+        assert!(!wrong.contains(5, 2, "4076"));
+
+        let paths = requests
+            .iter()
+            .take(4)
+            .map(|request| request.request_line)
+            .collect::<Vec<_>>();
+        assert_eq!(paths[1], "GET /2024/day/5 HTTP/1.1");
+    }
+}
@@ -0,0 +1,47 @@
+//! Fixtures shared by the tests of several modules
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{all_days, ErasedDay};
+
+/// The solution for the given day
+pub fn day(day: u8) -> Box<dyn ErasedDay> {
+    all_days()
+        .into_iter()
+        .find(|d| d.name().day == day)
+        .unwrap()
+}
+
+/// An empty directory under the system temp directory, removed again when dropped
+///
+/// Named from `name`, the process id and a counter, so tests running in parallel never share one.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "aoc_{}_{}_{}",
+            name,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
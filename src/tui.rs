@@ -0,0 +1,512 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Bar, BarChart, BarGroup, Block, List, ListItem, ListState, Paragraph, Row, Table,
+};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::answers::{AnswerStatus, AnswerStore};
+use crate::error::Phase;
+use crate::runner::{run_phase_isolated, PhaseOutput, PHASE_THREAD};
+use crate::{ErasedDay, Outcome, ParsedHandle};
+
+const PHASES: [Phase; 3] = [Phase::Parse, Phase::Part1, Phase::Part2];
+
+/// Number of runs made by a single press of the repeat key
+const REPEAT_RUNS: usize = 20;
+
+/// Number of buckets in the timing histogram
+const HISTOGRAM_BINS: usize = 10;
+
+/// The lower bound of each of `bins` equal-width buckets spanning the samples, and how many
+/// samples fall into each
+pub fn histogram(samples: &[Duration], bins: usize) -> Vec<(Duration, u64)> {
+    let (Some(min), Some(max)) = (samples.iter().min(), samples.iter().max()) else {
+        return Vec::new();
+    };
+
+    let width = (*max - *min) / bins as u32;
+    if width.is_zero() {
+        return vec![(*min, samples.len() as u64)];
+    }
+
+    let mut counts = vec![0; bins];
+    for sample in samples {
+        let bin = ((*sample - *min).as_nanos() / width.as_nanos()) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(bin, count)| (*min + width * bin as u32, count))
+        .collect()
+}
+
+/// Everything the TUI knows about a single day
+#[derive(Default)]
+struct DayState {
+    input: Option<Arc<str>>,
+
+    /// Kept so parts can be run again without re-parsing
+    parsed: Option<ParsedHandle>,
+
+    /// The most recent outcome of each part, and of the parse if it failed
+    parse_error: Option<Outcome>,
+    p1: Option<Outcome>,
+    p2: Option<Outcome>,
+
+    /// The time of every run of each phase, in the order of `PHASES`
+    times: [Vec<Duration>; 3],
+}
+
+/// Loads a day's input, given the status message to report any progress in
+type LoadInput<'a> = Box<dyn Fn(u8, &mut String) -> anyhow::Result<String> + 'a>;
+
+pub struct App<'a> {
+    days: Vec<Arc<dyn ErasedDay>>,
+    answers: AnswerStore,
+    load_input: LoadInput<'a>,
+    states: Vec<DayState>,
+    list: ListState,
+
+    /// How long to wait for each phase before giving up on it
+    timeout: Option<Duration>,
+
+    /// Index into `PHASES` of the phase shown in the histogram
+    histogram_phase: usize,
+
+    /// Shown at the bottom of the screen, e.g. the last error
+    message: String,
+}
+
+impl<'a> App<'a> {
+    /// `load_input` is called with a day number the first time each day is run, along with the
+    /// status message so it can report on anything it does, e.g. fetching the input
+    pub fn new(
+        days: Vec<Box<dyn ErasedDay>>,
+        answers: AnswerStore,
+        load_input: impl Fn(u8, &mut String) -> anyhow::Result<String> + 'a,
+    ) -> Self {
+        let states = days.iter().map(|_| DayState::default()).collect();
+        Self {
+            days: days.into_iter().map(Arc::from).collect(),
+            answers,
+            load_input: Box::new(load_input),
+            states,
+            list: ListState::default().with_selected(Some(0)),
+            timeout: None,
+            histogram_phase: 1,
+            message: String::new(),
+        }
+    }
+
+    /// Give up on any phase that takes longer than `timeout`, leaving it running in the background
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn selected(&self) -> usize {
+        self.list.selected().unwrap_or(0)
+    }
+
+    /// Parse the day's input, keeping the parsed input for later part runs
+    fn parse(&mut self, idx: usize) -> Option<ParsedHandle> {
+        let day = self.days[idx].clone();
+        let state = &mut self.states[idx];
+
+        if state.input.is_none() {
+            match (self.load_input)(day.name().day, &mut self.message) {
+                Ok(input) => state.input = Some(input.into()),
+                Err(err) => {
+                    self.message = format!("Day {:02}: {:#}", day.name().day, err);
+                    return None;
+                }
+            }
+        }
+        let input = state.input.clone().unwrap();
+
+        let (measurement, output) = run_phase_isolated(self.timeout, {
+            let day = day.clone();
+            move || day.parse(&input)
+        });
+        state.times[0].push(measurement.time);
+
+        let parsed = match &output {
+            PhaseOutput::Done(Ok(parsed)) => Some(parsed.clone()),
+            _ => None,
+        };
+        state.parse_error = parsed.is_none().then(|| {
+            let output = output.map(|result| result.map(|_| String::new()));
+            Outcome::from_phase(day.name().day, Phase::Parse, output)
+        });
+        state.parsed = parsed.clone();
+
+        // Outcomes from an earlier parse would otherwise be shown as if they came from this input
+        if parsed.is_none() {
+            state.p1 = Some(Outcome::Skipped);
+            state.p2 = Some(Outcome::Skipped);
+        }
+        parsed
+    }
+
+    /// Run one part of a day, parsing its input first if that hasn't been done yet
+    pub fn run_part(&mut self, idx: usize, part: u8) {
+        let parsed = match self.states[idx].parsed.clone() {
+            Some(parsed) => parsed,
+            None => match self.parse(idx) {
+                Some(parsed) => parsed,
+                None => return,
+            },
+        };
+
+        let day = self.days[idx].clone();
+        let name = day.name();
+        let (phase, (measurement, output)) = match part {
+            1 => (
+                Phase::Part1,
+                run_phase_isolated(self.timeout, move || day.part_1(&parsed)),
+            ),
+            _ => (
+                Phase::Part2,
+                run_phase_isolated(self.timeout, move || day.part_2(&parsed)),
+            ),
+        };
+
+        let state = &mut self.states[idx];
+        let outcome = Outcome::from_phase(name.day, phase, output);
+        if let Outcome::Error(err) = &outcome {
+            self.message = err.to_string();
+        }
+
+        state.times[part as usize].push(measurement.time);
+        match part {
+            1 => state.p1 = Some(outcome),
+            _ => state.p2 = Some(outcome),
+        }
+    }
+
+    /// Parse the day's input afresh and run both parts on it
+    pub fn run_day(&mut self, idx: usize) {
+        self.states[idx].parsed = None;
+        self.run_part(idx, 1);
+        self.run_part(idx, 2);
+    }
+
+    /// The latest outcome of a part, compared against its expected answer
+    fn part_status(&self, idx: usize, part: u8) -> Option<(&Outcome, AnswerStatus)> {
+        let state = &self.states[idx];
+        let outcome = match part {
+            1 => state.p1.as_ref(),
+            _ => state.p2.as_ref(),
+        }?;
+
+        let expected = self
+            .answers
+            .get(self.days[idx].name().day)
+            .and_then(|answers| answers.part(part));
+        Some((outcome, AnswerStatus::check(expected, outcome.answer())))
+    }
+
+    /// Handle a key press, returning false if the TUI should exit
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        let idx = self.selected();
+        self.message.clear();
+
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') if idx + 1 < self.days.len() => {
+                self.list.select_next()
+            }
+            KeyCode::Enter | KeyCode::Char('r') => self.run_day(idx),
+            KeyCode::Char('1') => self.run_part(idx, 1),
+            KeyCode::Char('2') => self.run_part(idx, 2),
+            KeyCode::Char('p') => {
+                self.parse(idx);
+            }
+            KeyCode::Char('n') => {
+                for _ in 0..REPEAT_RUNS {
+                    self.run_day(idx);
+                }
+            }
+            KeyCode::Char('a') => {
+                for idx in 0..self.days.len() {
+                    self.run_day(idx);
+                }
+            }
+            KeyCode::Tab => self.histogram_phase = (self.histogram_phase + 1) % PHASES.len(),
+            _ => (),
+        }
+
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [list, detail] =
+            Layout::horizontal([Constraint::Length(34), Constraint::Min(0)]).areas(main);
+        let [answers, histogram] =
+            Layout::vertical([Constraint::Length(6), Constraint::Min(0)]).areas(detail);
+
+        self.draw_list(frame, list);
+        self.draw_answers(frame, answers);
+        self.draw_histogram(frame, histogram);
+
+        let help_text = match self.message.is_empty() {
+            true => "↑/↓ select · r run · 1/2 run part · p parse · n run 20 times · a run all · tab histogram phase · q quit",
+            false => &self.message,
+        };
+        frame.render_widget(Paragraph::new(help_text), help);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let items = (0..self.days.len())
+            .map(|idx| {
+                let name = self.days[idx].name();
+                let markers = [1, 2]
+                    .map(|part| match self.part_status(idx, part) {
+                        Some((_, status)) => status.marker(),
+                        None => " ",
+                    })
+                    .join("");
+                ListItem::new(format!("{:02} {:26} {}", name.day, name.name, markers))
+            })
+            .collect::<Vec<_>>();
+
+        let list = List::new(items)
+            .block(Block::bordered().title("Days"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.list);
+    }
+
+    fn draw_answers(&self, frame: &mut Frame, area: Rect) {
+        let idx = self.selected();
+        let state = &self.states[idx];
+        let name = self.days[idx].name();
+
+        let mean = |times: &[Duration]| match times.len() {
+            0 => "-".to_string(),
+            n => format!("{:?}", times.iter().sum::<Duration>() / n as u32),
+        };
+
+        let mut rows = Vec::new();
+        for (idx_phase, phase) in PHASES.iter().enumerate() {
+            let times = &state.times[idx_phase];
+            let (answer, expected, style) = match phase {
+                Phase::Parse => (
+                    state
+                        .parse_error
+                        .as_ref()
+                        .map_or(String::new(), Outcome::to_string),
+                    String::new(),
+                    Style::default(),
+                ),
+                _ => {
+                    let part = idx_phase as u8;
+                    match self.part_status(idx, part) {
+                        Some((outcome, status)) => {
+                            let color = match status {
+                                AnswerStatus::Correct => Color::Green,
+                                AnswerStatus::Wrong { .. } => Color::Red,
+                                AnswerStatus::Unknown => Color::Yellow,
+                            };
+                            let expected = match status {
+                                AnswerStatus::Correct => "✓".to_string(),
+                                AnswerStatus::Wrong { expected } => expected,
+                                AnswerStatus::Unknown => "?".to_string(),
+                            };
+                            (outcome.to_string(), expected, Style::default().fg(color))
+                        }
+                        None => ("-".to_string(), String::new(), Style::default()),
+                    }
+                }
+            };
+
+            rows.push(
+                Row::new([
+                    phase.to_string(),
+                    answer,
+                    expected,
+                    times.len().to_string(),
+                    times.last().map_or("-".to_string(), |t| format!("{:?}", t)),
+                    mean(times),
+                ])
+                .style(style),
+            );
+        }
+
+        let widths = [
+            Constraint::Length(7),
+            Constraint::Min(16),
+            Constraint::Min(10),
+            Constraint::Length(5),
+            Constraint::Length(12),
+            Constraint::Length(12),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["Phase", "Answer", "Expected", "Runs", "Last", "Mean"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::bordered().title(format!("Day {:02}: {}", name.day, name.name)));
+        frame.render_widget(table, area);
+    }
+
+    fn draw_histogram(&self, frame: &mut Frame, area: Rect) {
+        let phase = PHASES[self.histogram_phase];
+        let times = &self.states[self.selected()].times[self.histogram_phase];
+
+        let bins = histogram(times, HISTOGRAM_BINS);
+        let bars = bins
+            .iter()
+            .map(|(start, count)| {
+                Bar::default()
+                    .value(*count)
+                    .label(Line::from(format!("{:>10}", format!("{:.1?}", start))))
+            })
+            .collect::<Vec<_>>();
+
+        let chart = BarChart::default()
+            .block(Block::bordered().title(format!("{} times over {} runs", phase, times.len())))
+            .direction(Direction::Horizontal)
+            .bar_width(1)
+            .bar_gap(0)
+            .data(BarGroup::default().bars(&bars));
+        frame.render_widget(chart, area);
+    }
+}
+
+/// Run the TUI until the user quits, restoring the terminal afterwards
+pub fn run(mut app: App) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+
+    // Panics in a day are caught and shown in the TUI, so keep them away from the hook ratatui
+    // installs, which would restore the terminal and scribble the message over the TUI. Any
+    // other panic is a bug in the TUI itself, and still gets the terminal back.
+    let restoring_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() != Some(PHASE_THREAD) {
+            restoring_hook(info);
+        }
+    }));
+
+    let result = event_loop(&mut app, &mut terminal);
+
+    // Dropping our hook, and ratatui's with it, puts the default hook back
+    drop(std::panic::take_hook());
+    ratatui::restore();
+    result
+}
+
+fn event_loop(app: &mut App, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_days;
+
+    fn test_app() -> App<'static> {
+        let days = all_days()
+            .into_iter()
+            .filter(|d| matches!(d.name().day, 2 | 3))
+            .collect();
+        let answers = AnswerStore::parse("[day_02]\npart_1 = \"2\"\npart_2 = \"5\"\n").unwrap();
+        App::new(days, answers, |day, _| match day {
+            2 => Ok(example!(2, 1).to_string()),
+            _ => anyhow::bail!("No input for day {day}"),
+        })
+    }
+
+    #[test]
+    fn test_histogram() {
+        let samples = [1, 2, 2, 3, 10].map(Duration::from_micros);
+        let bins = histogram(&samples, 3);
+        assert_eq!(
+            bins,
+            [
+                (Duration::from_micros(1), 4),
+                (Duration::from_micros(4), 0),
+                (Duration::from_micros(7), 1),
+            ]
+        );
+
+        assert_eq!(
+            histogram(&[Duration::from_micros(5); 3], 10),
+            [(Duration::from_micros(5), 3)]
+        );
+        assert!(histogram(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_run_parts() {
+        let mut app = test_app();
+
+        // A single part reuses the parse
+        app.run_part(0, 1);
+        app.run_part(0, 1);
+        assert_eq!(app.states[0].times.each_ref().map(Vec::len), [1, 2, 0]);
+
+        let (outcome, status) = app.part_status(0, 1).unwrap();
+        assert_eq!(outcome, &Outcome::Answer("2".to_string()));
+        assert_eq!(status, AnswerStatus::Correct);
+        assert!(app.part_status(0, 2).is_none());
+
+        // A full run parses again
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(app.states[0].times.each_ref().map(Vec::len), [2, 3, 1]);
+        assert_eq!(
+            app.part_status(0, 2).unwrap().1,
+            AnswerStatus::Wrong {
+                expected: "5".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_failed_reparse() {
+        let mut app = test_app();
+        app.run_day(0);
+        assert_eq!(app.states[0].p1, Some(Outcome::Answer("2".to_string())));
+
+        app.states[0].input = Some("not a report".into());
+        app.run_day(0);
+        assert!(matches!(app.states[0].parse_error, Some(Outcome::Error(_))));
+        assert_eq!(app.states[0].p1, Some(Outcome::Skipped));
+        assert_eq!(app.states[0].p2, Some(Outcome::Skipped));
+        assert!(app.states[0].times[1..]
+            .iter()
+            .all(|times| times.len() == 1));
+    }
+
+    #[test]
+    fn test_missing_input() {
+        let mut app = test_app();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected(), 1);
+
+        app.handle_key(KeyCode::Char('r'));
+        assert!(app.states[1].times.iter().all(Vec::is_empty));
+        assert!(app.message.contains("No input for day 3"));
+        assert!(app.handle_key(KeyCode::Char('x')));
+        assert!(!app.handle_key(KeyCode::Char('q')));
+    }
+}
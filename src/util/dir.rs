@@ -46,6 +46,16 @@ impl Dir {
     }
 
     pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    /// This direction's position in `ALL`, e.g. for indexing arrays built from it
+    pub const fn index(self) -> usize {
+        match self {
+            Dir::Up => 0,
+            Dir::Down => 1,
+            Dir::Left => 2,
+            Dir::Right => 3,
+        }
+    }
 }
 
 impl std::ops::Add<Dir> for Vec2 {
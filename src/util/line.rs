@@ -1,4 +1,4 @@
-use super::Vec2;
+use super::{gcm, Vec2};
 
 /// A bounded 2d line
 pub struct Line2 {
@@ -52,6 +52,118 @@ impl Line2 {
 
         Some(Vec2::new(x, y))
     }
+
+    /// This line extended without bound in both directions, if it spans more than one point
+    pub fn unbounded(&self) -> Option<UnboundedLine2> {
+        UnboundedLine2::through(self.start, self.end())
+    }
+}
+
+/// An unbounded 2d line through integer points, extending in both directions from `point`
+///
+/// Only constructed by `through`, so `dir` is never zero.
+pub struct UnboundedLine2 {
+    point: Vec2,
+
+    /// The step between consecutive integer points on the line, with coprime components
+    dir: Vec2,
+}
+
+/// Floor of a / b, for any signs
+const fn floor_div(a: i64, b: i64) -> i64 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+/// The range of steps t for which `p + d * t` is within 0..size, if any
+const fn axis_steps(p: i64, d: i64, size: i64) -> Option<(i64, i64)> {
+    if d == 0 {
+        return if p >= 0 && p < size {
+            Some((i64::MIN, i64::MAX))
+        } else {
+            None
+        };
+    }
+
+    // 0 <= p + d * t <= size - 1, where dividing through by a negative d flips the bounds
+    let (lo, hi) = if d > 0 {
+        (-p, size - 1 - p)
+    } else {
+        (size - 1 - p, -p)
+    };
+    let first = -floor_div(-lo, d);
+    let last = floor_div(hi, d);
+    if first <= last {
+        Some((first, last))
+    } else {
+        None
+    }
+}
+
+impl UnboundedLine2 {
+    /// The line through two distinct points, stepping through every integer point between them
+    pub fn through(a: Vec2, b: Vec2) -> Option<Self> {
+        let delta = b - a;
+        let divisor = gcm(delta.x.abs(), delta.y.abs());
+        if divisor == 0 {
+            return None;
+        }
+
+        Some(Self {
+            point: a,
+            dir: Vec2::new(delta.x / divisor, delta.y / divisor),
+        })
+    }
+
+    pub const fn point(&self) -> Vec2 {
+        self.point
+    }
+
+    pub const fn dir(&self) -> Vec2 {
+        self.dir
+    }
+
+    /// The point `steps` steps of `dir` along the line from `point`, which may be negative
+    pub const fn at(&self, steps: i64) -> Vec2 {
+        self.point.const_add(self.dir.const_scalar_mul(steps))
+    }
+
+    /// How many steps of `dir` along the line `pos` is from `point`, if it's on the line
+    pub fn steps_to(&self, pos: Vec2) -> Option<i64> {
+        let delta = pos - self.point;
+        let steps = if self.dir.x != 0 {
+            delta.x / self.dir.x
+        } else {
+            delta.y / self.dir.y
+        };
+        (self.at(steps) == pos).then_some(steps)
+    }
+
+    /// The bounded segment of this line covering `len` steps from `steps` steps along it
+    pub const fn segment(&self, steps: i64, len: usize) -> Line2 {
+        Line2 {
+            start: self.at(steps),
+            dir: self.dir,
+            len,
+        }
+    }
+
+    /// Every integer point on the line inside a map that spans (0, 0) to `map_size`, in order
+    /// along `dir`
+    pub fn rasterize(&self, map_size: Vec2) -> impl Iterator<Item = Vec2> + '_ {
+        let x = axis_steps(self.point.x, self.dir.x, map_size.x);
+        let y = axis_steps(self.point.y, self.dir.y, map_size.y);
+        let (first, last) = match (x, y) {
+            (Some(x), Some(y)) => (x.0.max(y.0), x.1.min(y.1)),
+            _ => (0, -1),
+        };
+
+        (first..=last).map(move |steps| self.at(steps))
+    }
 }
 
 #[cfg(test)]
@@ -67,7 +179,7 @@ mod tests {
         };
 
         assert_eq!(l.x_range(), (10, 13));
-        assert_eq!(l.y_range(), (20, 2));
+        assert_eq!(l.y_range(), (20, 20));
     }
     
     #[test]
@@ -89,4 +201,37 @@ mod tests {
         l2.len = 4;
         assert_eq!(l1.intersection(&l2), None);
     }
+
+    #[test]
+    fn test_unbounded_line() {
+        let line = UnboundedLine2::through(Vec2::new(1, 7), Vec2::new(5, 1)).unwrap();
+        assert_eq!(line.dir, Vec2::new(2, -3));
+        assert_eq!(line.steps_to(Vec2::new(5, 1)), Some(2));
+        assert_eq!(line.steps_to(Vec2::new(-1, 10)), Some(-1));
+        assert_eq!(line.steps_to(Vec2::new(2, 5)), None);
+        assert_eq!(line.segment(-1, 3).end(), Vec2::new(5, 1));
+
+        let points = line.rasterize(Vec2::new(8, 8)).collect::<Vec<_>>();
+        assert_eq!(points, [Vec2::new(1, 7), Vec2::new(3, 4), Vec2::new(5, 1)]);
+
+        let vertical = Line2 {
+            start: Vec2::new(2, 2),
+            dir: Vec2::new(0, -1),
+            len: 2,
+        }
+        .unbounded()
+        .unwrap();
+        assert_eq!(vertical.point(), Vec2::new(2, 2));
+        assert_eq!(vertical.dir(), Vec2::new(0, -1));
+        assert_eq!(vertical.rasterize(Vec2::new(3, 3)).count(), 3);
+        assert_eq!(vertical.rasterize(Vec2::new(2, 3)).count(), 0);
+
+        assert!(UnboundedLine2::through(Vec2::new(1, 1), Vec2::new(1, 1)).is_none());
+        let point = Line2 {
+            start: Vec2::new(1, 1),
+            dir: Vec2::new(1, 0),
+            len: 0,
+        };
+        assert!(point.unbounded().is_none());
+    }
 }
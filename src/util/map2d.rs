@@ -1,4 +1,11 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use super::{Dir, Vec2};
+use crate::error::InputError;
 
 pub trait Map2dExt<Tile> {
     fn size(&self) -> Vec2;
@@ -20,6 +27,21 @@ pub trait Map2dExt<Tile> {
             println!();
         }
     }
+
+    /// A frame for visualizing this map, colouring each tile with `f`
+    fn frame(&self, f: impl Fn(Tile) -> Rgb) -> Frame
+    where
+        Tile: Copy,
+    {
+        let size = self.size();
+        let mut pixels = Vec::with_capacity((size.x * size.y) as usize);
+        for y in 0..size.y {
+            for x in 0..size.x {
+                pixels.push(f(self.get(Vec2::new(x, y)).unwrap()));
+            }
+        }
+        Frame { size, pixels }
+    }
 }
 
 #[derive(Clone)]
@@ -37,14 +59,44 @@ impl<Tile> Map2d<Tile> {
         Self { size, data }
     }
 
-    pub fn parse_grid(s: &str, f: impl Fn(char) -> Tile) -> Self {
-        let size_x = s.lines().next().unwrap().len();
-        let size_y = s.lines().count();
-        let size = Vec2::new(size_x as i64, size_y as i64);
+    /// Parse a rectangular grid with one tile per char, and one row per line
+    pub fn parse_grid(s: &str, f: impl Fn(char) -> Tile) -> Result<Self, InputError> {
+        Self::try_parse_grid(s, |c| Some(f(c)))
+    }
 
-        let data = s.chars().filter(|&c| c != '\n').map(f).collect::<Vec<_>>();
+    /// As `parse_grid`, but any char for which `f` returns `None` is rejected
+    pub fn try_parse_grid(s: &str, f: impl Fn(char) -> Option<Tile>) -> Result<Self, InputError> {
+        let size_x = s
+            .lines()
+            .next()
+            .ok_or_else(|| InputError::new("Empty grid"))?
+            .chars()
+            .count();
 
-        Self { size, data }
+        let mut data = Vec::with_capacity(s.len());
+        let mut size_y = 0;
+        for (idx, line) in s.lines().enumerate() {
+            let line_number = idx + 1;
+            let mut row_len = 0;
+            for (col, c) in line.chars().enumerate() {
+                let tile = f(c).ok_or_else(|| {
+                    InputError::at(line_number, col + 1, format!("Unexpected character {c:?}"))
+                })?;
+                data.push(tile);
+                row_len += 1;
+            }
+
+            if row_len != size_x {
+                return Err(InputError::at_line(
+                    line_number,
+                    format!("Row has length {row_len}, expected {size_x}"),
+                ));
+            }
+            size_y += 1;
+        }
+
+        let size = Vec2::new(size_x as i64, size_y);
+        Ok(Self { size, data })
     }
 
     pub fn index_of(&self, pos: Vec2) -> Option<usize> {
@@ -151,3 +203,458 @@ impl<'a, Tile> Map2dExt<Tile> for RotatedMap2d<'a, Tile> {
         self.map.get_mut(self.source_pos(pos))
     }
 }
+
+/// A colour as red, green and blue
+pub type Rgb = [u8; 3];
+
+/// A snapshot of a map during a solve with one colour per tile, see `record_frame`
+///
+/// Overlays such as positions and paths are drawn on top with the builder methods, e.g.
+/// `map.frame(colour).path(route, YELLOW).point(guard, RED)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub size: Vec2,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(size: Vec2, background: Rgb) -> Self {
+        let pixels = vec![background; (size.x * size.y) as usize];
+        Self { size, pixels }
+    }
+
+    pub fn get(&self, pos: Vec2) -> Option<Rgb> {
+        pos.inside_map(self.size)
+            .then(|| self.pixels[(pos.x + pos.y * self.size.x) as usize])
+    }
+
+    /// Colour a single tile, ignoring positions off the map
+    pub fn point(mut self, pos: Vec2, color: Rgb) -> Self {
+        if pos.inside_map(self.size) {
+            self.pixels[(pos.x + pos.y * self.size.x) as usize] = color;
+        }
+        self
+    }
+
+    /// Colour each of the given tiles, e.g. the current position of every robot
+    pub fn points(self, positions: impl IntoIterator<Item = Vec2>, color: Rgb) -> Self {
+        positions
+            .into_iter()
+            .fold(self, |frame, pos| frame.point(pos, color))
+    }
+
+    /// Colour the tiles along a path, filling in the straight or diagonal line between each pair
+    /// of consecutive positions
+    pub fn path(mut self, path: impl IntoIterator<Item = Vec2>, color: Rgb) -> Self {
+        let mut previous: Option<Vec2> = None;
+        for pos in path {
+            let mut current = previous.unwrap_or(pos);
+            let step = Vec2::new((pos.x - current.x).signum(), (pos.y - current.y).signum());
+            while current != pos {
+                self = self.point(current, color);
+                current += step;
+            }
+            self = self.point(pos, color);
+            previous = Some(pos);
+        }
+        self
+    }
+
+    /// The frame as rows of RGB bytes, with each tile drawn as a `scale` pixel square
+    pub fn to_rgb_bytes(&self, scale: usize) -> Vec<u8> {
+        let width = self.size.x as usize;
+        let mut bytes = Vec::with_capacity(self.pixels.len() * scale * scale * 3);
+        for row in self.pixels.chunks(width.max(1)) {
+            for _ in 0..scale {
+                for pixel in row {
+                    for _ in 0..scale {
+                        bytes.extend_from_slice(pixel);
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Save the frame as a single PNG, PPM or PBM image, chosen by the extension of `path`
+    ///
+    /// Small maps are scaled up as for `FrameFormat::write`. PBM images are black and white, with
+    /// every tile that isn't black drawn in black on white.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.save_scaled(path, image_scale(self.size))
+    }
+
+    fn save_scaled(&self, path: &Path, scale: usize) -> io::Result<()> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        if !matches!(extension, Some("png" | "ppm" | "pbm")) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported image format {}", path.display()),
+            ));
+        }
+
+        let width = self.size.x as usize * scale;
+        let height = self.size.y as usize * scale;
+        let file = BufWriter::new(File::create(path)?);
+        match extension {
+            Some("png") => write_png(file, &self.to_rgb_bytes(scale), width, height),
+            Some("ppm") => write_ppm(file, &self.to_rgb_bytes(scale), width, height),
+            _ => write_pbm(file, &self.to_rgb_bytes(scale), width, height),
+        }
+    }
+}
+
+struct Recorder {
+    frames: Vec<Frame>,
+    max_frames: usize,
+
+    /// Only every `stride`th frame offered is kept, doubling each time `max_frames` is reached
+    stride: usize,
+    offered: usize,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Whether frames recorded on this thread are being kept, for days that need to do extra work to
+/// visualize themselves
+pub fn is_recording() -> bool {
+    RECORDER.with_borrow(Option::is_some)
+}
+
+/// Record a frame of the solve in progress if it's being visualized, e.g. with `--visualize`
+///
+/// `frame` is only called for frames that are kept, so this is cheap to call on every step of a
+/// solve. Long solves are thinned out by dropping every other frame whenever the recorder's
+/// frame limit is reached.
+pub fn record_frame(frame: impl FnOnce() -> Frame) {
+    RECORDER.with_borrow_mut(|recorder| {
+        let Some(recorder) = recorder else {
+            return;
+        };
+
+        if recorder.offered % recorder.stride == 0 {
+            recorder.frames.push(frame());
+            if recorder.frames.len() > recorder.max_frames {
+                let mut idx = 0;
+                recorder.frames.retain(|_| {
+                    idx += 1;
+                    idx % 2 == 1
+                });
+                recorder.stride *= 2;
+            }
+        }
+        recorder.offered += 1;
+    });
+}
+
+/// Run `f`, returning the frames it records on this thread, thinned out to at most `max_frames`
+pub fn recording<T>(max_frames: usize, f: impl FnOnce() -> T) -> (T, Vec<Frame>) {
+    let recorder = Recorder {
+        frames: Vec::new(),
+        max_frames: max_frames.max(1),
+        stride: 1,
+        offered: 0,
+    };
+
+    let outer = RECORDER.replace(Some(recorder));
+    let value = f();
+    let recorder = RECORDER.replace(outer);
+    (value, recorder.map(|r| r.frames).unwrap_or_default())
+}
+
+/// File formats recorded frames can be written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FrameFormat {
+    /// A single animated PNG
+    Apng,
+    /// A single animated GIF
+    Gif,
+    /// A directory of numbered PNG images
+    Png,
+    /// A directory of numbered binary PPM images
+    Ppm,
+    /// A directory of numbered binary PBM images, see `Frame::save`
+    Pbm,
+}
+
+impl FrameFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FrameFormat::Apng | FrameFormat::Png => "png",
+            FrameFormat::Gif => "gif",
+            FrameFormat::Ppm => "ppm",
+            FrameFormat::Pbm => "pbm",
+        }
+    }
+
+    /// Write `frames` under `path`, as a single animation file with this format's extension, or
+    /// as a directory of images named `frame_00000.png` and so on, returning the path written
+    ///
+    /// Small maps are scaled up so that each frame is at least around 512 pixels across.
+    pub fn write(self, frames: &[Frame], path: &Path, delay: Duration) -> io::Result<PathBuf> {
+        let Some(first) = frames.first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No frames to write",
+            ));
+        };
+        if frames.iter().any(|frame| frame.size != first.size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Frames have different sizes",
+            ));
+        }
+
+        let scale = image_scale(first.size);
+
+        match self {
+            FrameFormat::Apng | FrameFormat::Gif => {
+                let width = first.size.x as usize * scale;
+                let height = first.size.y as usize * scale;
+                let path = path.with_extension(self.extension());
+                let file = BufWriter::new(File::create(&path)?);
+                if self == FrameFormat::Apng {
+                    write_apng(file, frames, scale, width, height, delay)?;
+                } else {
+                    write_gif(file, frames, scale, width, height, delay)?;
+                }
+                Ok(path)
+            }
+            FrameFormat::Png | FrameFormat::Ppm | FrameFormat::Pbm => {
+                std::fs::create_dir_all(path)?;
+                for (idx, frame) in frames.iter().enumerate() {
+                    let name = format!("frame_{:05}.{}", idx, self.extension());
+                    frame.save_scaled(&path.join(name), scale)?;
+                }
+                Ok(path.to_path_buf())
+            }
+        }
+    }
+}
+
+/// How much to scale up a map so that it's at least around 512 pixels across
+fn image_scale(size: Vec2) -> usize {
+    (512 / size.x.max(size.y).max(1)).max(1) as usize
+}
+
+fn png_encoder<W: Write>(w: W, width: usize, height: usize) -> png::Encoder<'static, W> {
+    let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+}
+
+fn write_png(w: impl Write, bytes: &[u8], width: usize, height: usize) -> io::Result<()> {
+    let mut writer = png_encoder(w, width, height).write_header()?;
+    writer.write_image_data(bytes)?;
+    Ok(writer.finish()?)
+}
+
+fn write_apng(
+    w: impl Write,
+    frames: &[Frame],
+    scale: usize,
+    width: usize,
+    height: usize,
+    delay: Duration,
+) -> io::Result<()> {
+    let mut encoder = png_encoder(w, width, height);
+    // Zero plays forever
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(delay.as_millis().min(u16::MAX as u128) as u16, 1000)?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(&frame.to_rgb_bytes(scale))?;
+    }
+    Ok(writer.finish()?)
+}
+
+fn write_gif(
+    w: impl Write,
+    frames: &[Frame],
+    scale: usize,
+    width: usize,
+    height: usize,
+    delay: Duration,
+) -> io::Result<()> {
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Frames are too large for a GIF",
+        ));
+    };
+
+    let mut encoder = gif::Encoder::new(w, width, height, &[]).map_err(io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(io::Error::other)?;
+
+    // GIF delays are in hundredths of a second
+    let delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+    for frame in frames {
+        let mut gif_frame =
+            gif::Frame::from_rgb_speed(width, height, &frame.to_rgb_bytes(scale), 10);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+fn write_pbm(mut w: impl Write, bytes: &[u8], width: usize, height: usize) -> io::Result<()> {
+    write!(w, "P4\n{} {}\n", width, height)?;
+
+    // Each row is packed into bytes, most significant bit first, with 1 for ink
+    let mut packed = vec![0u8; width.div_ceil(8)];
+    for row in bytes.chunks(width * 3).take(height) {
+        packed.fill(0);
+        for (x, pixel) in row.chunks(3).enumerate() {
+            if pixel != [0; 3] {
+                packed[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        w.write_all(&packed)?;
+    }
+    w.flush()
+}
+
+fn write_ppm(mut w: impl Write, bytes: &[u8], width: usize, height: usize) -> io::Result<()> {
+    write!(w, "P6\n{} {}\n255\n", width, height)?;
+    w.write_all(bytes)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_parse_grid() {
+        let map = Map2d::parse_grid("ab\ncd\nef", std::convert::identity).unwrap();
+        assert_eq!(map.size, Vec2::new(2, 3));
+        assert_eq!(map.get(Vec2::new(1, 2)), Some('f'));
+
+        assert_eq!(
+            Map2d::parse_grid("ab\nc\nef", std::convert::identity).err(),
+            Some(InputError::at_line(2, "Row has length 1, expected 2"))
+        );
+        assert_eq!(
+            Map2d::try_parse_grid("12\n3x", |c| c.to_digit(10)).err(),
+            Some(InputError::at(2, 2, "Unexpected character 'x'"))
+        );
+    }
+
+    const RED: Rgb = [255, 0, 0];
+    const WHITE: Rgb = [255, 255, 255];
+
+    #[test]
+    fn test_frame() {
+        let map = Map2d::parse_grid("#..\n...\n..#", |c| c == '#').unwrap();
+        let frame = map
+            .frame(|wall| if wall { WHITE } else { [0; 3] })
+            .path([Vec2::new(0, 1), Vec2::new(2, 1), Vec2::new(2, 2)], RED)
+            .point(Vec2::new(1, 0), RED)
+            .point(Vec2::new(5, 5), RED);
+
+        let render = (0..3)
+            .map(|y| {
+                (0..3)
+                    .map(|x| match frame.get(Vec2::new(x, y)).unwrap() {
+                        WHITE => '#',
+                        RED => 'o',
+                        _ => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(render, ["#o.", "ooo", "..o"]);
+
+        let bytes = Frame::new(Vec2::new(2, 1), WHITE)
+            .point(Vec2::new(1, 0), RED)
+            .to_rgb_bytes(2);
+        assert_eq!(
+            bytes,
+            [WHITE, WHITE, RED, RED, WHITE, WHITE, RED, RED].concat()
+        );
+    }
+
+    #[test]
+    fn test_recording() {
+        let frame = |x| Frame::new(Vec2::new(x, 1), WHITE);
+
+        record_frame(|| panic!("Frames shouldn't be built when nothing is recording"));
+
+        let (value, frames) = recording(4, || {
+            assert!(is_recording());
+            for x in 1..=10 {
+                record_frame(|| frame(x));
+            }
+            7
+        });
+        assert_eq!(value, 7);
+        assert!(!is_recording());
+
+        // Thinned to every 4th frame after the limit was passed twice
+        let widths = frames.iter().map(|f| f.size.x).collect::<Vec<_>>();
+        assert_eq!(widths, [1, 5, 9]);
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let tmp = TempDir::new("frames");
+        let root = tmp.path();
+
+        let frames = [
+            Frame::new(Vec2::new(2, 1), WHITE),
+            Frame::new(Vec2::new(2, 1), RED),
+        ];
+        let delay = Duration::from_millis(50);
+
+        let apng = FrameFormat::Apng
+            .write(&frames, &root.join("anim"), delay)
+            .unwrap();
+        let gif = FrameFormat::Gif
+            .write(&frames, &root.join("anim"), delay)
+            .unwrap();
+        let ppm = FrameFormat::Ppm
+            .write(&frames, &root.join("ppm"), delay)
+            .unwrap();
+
+        let decoder = png::Decoder::new(File::open(&apng).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (512, 256));
+        assert_eq!(info.animation_control.unwrap().num_frames, 2);
+
+        assert!(std::fs::metadata(&gif).unwrap().len() > 0);
+
+        let second = std::fs::read(ppm.join("frame_00001.ppm")).unwrap();
+        let header = b"P6\n512 256\n255\n";
+        assert_eq!(&second[..header.len()], header);
+        assert_eq!(&second[header.len()..header.len() + 3], RED);
+        assert_eq!(second.len(), header.len() + 512 * 256 * 3);
+
+        let pbm = root.join("point.pbm");
+        Frame::new(Vec2::new(256, 1), [0; 3])
+            .point(Vec2::new(1, 0), RED)
+            .save(&pbm)
+            .unwrap();
+        let pbm = std::fs::read(pbm).unwrap();
+        let header = b"P4\n512 2\n";
+        assert_eq!(&pbm[..header.len()], header);
+        assert_eq!(&pbm[header.len()..header.len() + 2], [0b0011_0000, 0]);
+        assert_eq!(pbm.len(), header.len() + 2 * 64);
+        assert!(frames[0].save(&root.join("frame.jpg")).is_err());
+
+        assert!(FrameFormat::Png
+            .write(
+                &[frames[0].clone(), Frame::new(Vec2::new(1, 1), RED)],
+                root,
+                delay
+            )
+            .is_err());
+    }
+}
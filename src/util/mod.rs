@@ -8,7 +8,7 @@ pub mod line;
 
 pub use combinatorial::*;
 pub use dir::Dir;
-pub use map2d::{Map2d, Map2dExt, RotatedMap2d};
+pub use map2d::{Frame, Map2d, Map2dExt, Rgb, RotatedMap2d};
 pub use numbers::*;
 pub use vec2::Vec2;
-pub use line::Line2;
+pub use line::{Line2, UnboundedLine2};
@@ -24,6 +24,26 @@ where
     result
 }
 
+/// Find the smallest non-negative `x` with `x % m1 == a1` and `x % m2 == a2`, using the Chinese
+/// remainder theorem. Returns `None` if the moduli aren't coprime.
+pub fn crt(a1: i64, m1: i64, a2: i64, m2: i64) -> Option<i64> {
+    // Extended Euclid, maintaining old_s * m1 == old_r (mod m2)
+    let (mut old_r, mut r) = (m1, m2);
+    let (mut old_s, mut s) = (1, 0);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    if old_r != 1 {
+        return None;
+    }
+
+    // x = a1 + m1 * k, where k = (a2 - a1) * m1^-1 (mod m2)
+    let k = ((a2 - a1) % m2 * old_s % m2 + m2) % m2;
+    Some((a1 + m1 * k).rem_euclid(m1 * m2))
+}
+
 /// Return the number of ways to choose k items from n items without repetition
 /// and without order.
 pub fn binomial_coefficient(n: i64, k: i64) -> i64 {
@@ -44,4 +64,12 @@ mod tests {
         assert_eq!(super::binomial_coefficient(5, 1), 5);
         assert_eq!(super::binomial_coefficient(5, 0), 1);
     }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(super::crt(2, 3, 3, 5), Some(8));
+        assert_eq!(super::crt(0, 101, 0, 103), Some(0));
+        assert_eq!(super::crt(64, 101, 10, 103), Some(2791));
+        assert_eq!(super::crt(1, 4, 3, 6), None);
+    }
 }
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::inputs::{input_set_dir, InputCase, InputSource};
+use crate::puzzle::puzzle_dir;
+use crate::report::{name_cell, result_cell};
+use crate::{Outcome, RunResult};
+
+/// The modification time of every file under the watched paths
+pub type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+/// Everything a day's results depend on that can change while watching: its main input, its
+/// input set directory, and its saved puzzle examples
+pub fn watched_paths(input_root: &Path, puzzle_root: &Path, day: u8) -> Vec<PathBuf> {
+    vec![
+        input_root.join(format!("input_{}.txt", day)),
+        input_set_dir(input_root, day),
+        puzzle_dir(puzzle_root, day),
+    ]
+}
+
+/// Each saved puzzle example for a day, e.g. `puzzles/day_5/example_1.txt`, as an extra input
+///
+/// Expected answers are read from a sibling `.answers.toml` file, as for the input set.
+pub fn example_cases(puzzle_root: &Path, day: u8) -> anyhow::Result<Vec<InputCase>> {
+    let mut cases = Vec::new();
+    for path in files_under(&puzzle_dir(puzzle_root, day)) {
+        let Some(label) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if !label.starts_with("example_") || path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+
+        let expected = crate::answers::PartAnswers::load(&path.with_extension("answers.toml"))?;
+        cases.push(InputCase {
+            label: label.to_string(),
+            source: InputSource::File(path),
+            expected: expected.unwrap_or_default(),
+        });
+    }
+
+    Ok(cases)
+}
+
+/// Every file under `path` in name order, just `path` itself if it's a file, or nothing if it
+/// doesn't exist
+fn files_under(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return if path.is_file() {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        };
+    };
+
+    let mut files = entries
+        .flatten()
+        .flat_map(|entry| files_under(&entry.path()))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+pub fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    paths
+        .iter()
+        .flat_map(|path| files_under(path))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+/// Files that have been created, modified or removed between two snapshots
+pub fn changed(before: &Snapshot, after: &Snapshot) -> Vec<PathBuf> {
+    let modified = after
+        .iter()
+        .filter(|(path, modified)| before.get(*path) != Some(modified))
+        .map(|(path, _)| path.clone());
+    let removed = before
+        .keys()
+        .filter(|path| !after.contains_key(*path))
+        .cloned();
+
+    let mut changed = modified.chain(removed).collect::<Vec<_>>();
+    changed.sort();
+    changed
+}
+
+/// A part's result, noting the previous result if it has changed
+fn diff_cell(result: &Outcome, previous: Option<&Outcome>, cell: String) -> (String, bool) {
+    match previous {
+        Some(previous) if previous != result => (format!("{} (was {})", cell, previous), true),
+        _ => (cell, false),
+    }
+}
+
+/// A phase's time, with how much it has changed by since the previous run if there was one
+fn time_cell(time: Duration, previous: Option<Duration>) -> String {
+    match previous {
+        Some(previous) if !previous.is_zero() => {
+            let change = time.as_secs_f64() / previous.as_secs_f64() - 1.0;
+            format!("{:?} ({:+.1}%)", time, change * 100.0)
+        }
+        _ => format!("{:?}", time),
+    }
+}
+
+/// Results table for a run in watch mode, noting any answers that have changed since the
+/// previous run, and how much the time of each phase has changed by
+///
+/// Changed answers are highlighted in bold yellow if `highlight` is set, e.g. when writing to a
+/// terminal.
+pub fn write_diff_table(
+    w: &mut impl Write,
+    previous: &[RunResult],
+    current: &[RunResult],
+    highlight: bool,
+) -> std::io::Result<()> {
+    if current.is_empty() {
+        return Ok(());
+    }
+
+    const HEADERS: [&str; 8] = [
+        "Day",
+        "Name",
+        "P1 result",
+        "P2 result",
+        "Parse time",
+        "P1 time",
+        "P2 time",
+        "Total time",
+    ];
+
+    let rows = current
+        .iter()
+        .map(|result| {
+            let previous = previous
+                .iter()
+                .find(|p| p.name.day == result.name.day && p.input == result.input);
+
+            let part_time =
+                |outcome: &Outcome, time: Duration, previous: Option<Duration>| match outcome {
+                    Outcome::Skipped => "-".to_string(),
+                    _ => time_cell(time, previous),
+                };
+            let total_time = match previous {
+                Some(previous) => time_cell(result.total_time(), Some(previous.total_time())),
+                None => format!("{:?} (new)", result.total_time()),
+            };
+
+            [
+                (format!("{:02}", result.name.day), false),
+                (name_cell(result), false),
+                diff_cell(
+                    &result.p1_result,
+                    previous.map(|p| &p.p1_result),
+                    result_cell(&result.p1_result, &result.p1_status),
+                ),
+                diff_cell(
+                    &result.p2_result,
+                    previous.map(|p| &p.p2_result),
+                    result_cell(&result.p2_result, &result.p2_status),
+                ),
+                (
+                    time_cell(result.parse_time, previous.map(|p| p.parse_time)),
+                    false,
+                ),
+                (
+                    part_time(
+                        &result.p1_result,
+                        result.p1_time,
+                        previous.map(|p| p.p1_time),
+                    ),
+                    false,
+                ),
+                (
+                    part_time(
+                        &result.p2_result,
+                        result.p2_time,
+                        previous.map(|p| p.p2_time),
+                    ),
+                    false,
+                ),
+                (total_time, false),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, (cell, _)) in widths.iter_mut().zip(row.iter()) {
+            *width = std::cmp::max(*width, cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &[(&str, bool)]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|((cell, changed), width)| {
+                let padded = format!("{:width$}", cell, width = width);
+                match changed & highlight {
+                    true => format!("\x1b[1;33m{}\x1b[0m", padded),
+                    false => padded,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let header = format_row(&HEADERS.map(|header| (header, false)));
+    writeln!(w, "{}", header)?;
+    writeln!(w, "{}", "-".repeat(header.len()))?;
+
+    for row in &rows {
+        writeln!(
+            w,
+            "{}",
+            format_row(
+                &row.each_ref()
+                    .map(|(cell, changed)| (cell.as_str(), *changed))
+            )
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::inputs::MAIN_INPUT;
+    use crate::test_util::TempDir;
+    use crate::DayName;
+
+    fn result(day: u8, input: &str, p1: &str, p1_micros: u64) -> RunResult {
+        RunResult {
+            name: DayName {
+                name: "Print Queue",
+                day,
+            },
+            input: input.to_string(),
+            parse_time: Duration::from_micros(10),
+            p1_time: Duration::from_micros(p1_micros),
+            p2_time: Duration::ZERO,
+            p1_result: Outcome::Answer(p1.to_string()),
+            p2_result: Outcome::Skipped,
+            parse_alloc: None,
+            p1_alloc: None,
+            p2_alloc: None,
+            parse_counters: None,
+            p1_counters: None,
+            p2_counters: None,
+            p1_status: None,
+            p2_status: None,
+        }
+    }
+
+    fn render(previous: &[RunResult], current: &[RunResult], highlight: bool) -> String {
+        let mut out = Vec::new();
+        write_diff_table(&mut out, previous, current, highlight).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_diff_table() {
+        let previous = [result(5, MAIN_INPUT, "143", 100)];
+        let current = [
+            result(5, MAIN_INPUT, "144", 150),
+            result(5, "example_1", "143", 10),
+        ];
+
+        let text = render(&previous, &current, false);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "Day | Name                    | P1 result     | P2 result | Parse time   | P1 time        \
+             | P2 time | Total time"
+        );
+        assert_eq!(
+            lines[2],
+            "05  | Print Queue             | 144 (was 143) | -         | 10µs (+0.0%) | 150µs (+50.0%) \
+             | -       | 160µs (+45.5%)"
+        );
+        assert_eq!(
+            lines[3],
+            "05  | Print Queue [example_1] | 143           | -         | 10µs         | 10µs           \
+             | -       | 20µs (new)"
+        );
+
+        let text = render(&previous, &current, true);
+        assert!(text.contains("| \x1b[1;33m144 (was 143)\x1b[0m | -"));
+        assert_eq!(text.matches("\x1b[1;33m").count(), 1);
+    }
+
+    #[test]
+    fn test_changed() {
+        let tmp = TempDir::new("watch");
+        let root = tmp.path();
+        let paths = watched_paths(root, &root.join("puzzles"), 5);
+        std::fs::create_dir_all(&paths[1]).unwrap();
+        std::fs::write(&paths[0], "1|2\n").unwrap();
+
+        let before = snapshot(&paths);
+        assert_eq!(before.len(), 1);
+
+        let example = paths[1].join("example.txt");
+        std::fs::write(&example, "1|2\n").unwrap();
+        let after = snapshot(&paths);
+        assert_eq!(changed(&before, &after), std::slice::from_ref(&example));
+
+        std::fs::remove_file(&example).unwrap();
+        let removed = snapshot(&paths);
+
+        assert_eq!(changed(&after, &removed), [example]);
+        assert!(changed(&before, &removed).is_empty());
+    }
+
+    #[test]
+    fn test_example_cases() {
+        let tmp = TempDir::new("watch_examples");
+        let root = tmp.path();
+        let dir = puzzle_dir(root, 5);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("example_2.txt"), "").unwrap();
+        std::fs::write(dir.join("example_1.txt"), "").unwrap();
+        std::fs::write(dir.join("example_1.answers.toml"), "part_1 = \"143\"\n").unwrap();
+        std::fs::write(dir.join("puzzle.md"), "").unwrap();
+
+        let cases = example_cases(root, 5).unwrap();
+
+        let labels = cases.iter().map(|c| c.label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, ["example_1", "example_2"]);
+        assert_eq!(cases[0].expected.part_1.as_deref(), Some("143"));
+    }
+}